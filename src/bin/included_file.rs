@@ -2,7 +2,7 @@ extern crate env_logger;
 #[macro_use]
 extern crate log;
 extern crate rlisp;
-use rlisp::lisp::Lisp;
+use rlisp::lisp::{self, Lisp};
 use rlisp::repl::string_repl::StringRepl;
 use rlisp::repl::Repl;
 
@@ -13,12 +13,15 @@ const LISP_SOURCE: &str = include_str!(concat!(
 
 fn main() {
     env_logger::init();
-    let mut lisp = Lisp::default();
-    info!("finished creating lisp");
-    let mut input = String::from(LISP_SOURCE);
-    let mut repl = StringRepl::from(&mut lisp);
-    let mut output = String::new();
-    repl.run(&mut input, &mut output, &mut String::new())
-        .unwrap();
-    println!("{}", output);
+    lisp::on_eval_stack(|| {
+        let mut lisp = Lisp::default();
+        lisp.begin_source("lisp_source/try-catch.rlsp");
+        info!("finished creating lisp");
+        let mut input = String::from(LISP_SOURCE);
+        let mut repl = StringRepl::from(&mut lisp);
+        let mut output = String::new();
+        repl.run(&mut input, &mut output, &mut String::new())
+            .unwrap();
+        println!("{}", output);
+    });
 }
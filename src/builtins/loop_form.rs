@@ -0,0 +1,308 @@
+/*
+The special form `loop` implements a subset of Common Lisp's `loop`:
+
+    (loop for VAR in LIST
+          for VAR from START to END [by STEP]
+          [when TEST] collect FORM
+          [when TEST] sum FORM
+          [when TEST] do FORM...
+          finally FORM...)
+
+`STEP` must be a positive number. `eval_loop` first parses the clauses
+into `Loop`, without evaluating anything, and then runs it.
+ */
+
+use lisp;
+use types::*;
+use types::conversions::*;
+use evaluator::Evaluator;
+use symbols_table::SymbolLookup;
+use lisp::allocate::AllocObject;
+use lisp::stack_storage::Stack;
+use list::ListOps;
+
+const KEYWORDS: &[&[u8]] = &[
+    b"for", b"in", b"from", b"to", b"by", b"collect", b"sum", b"when", b"do", b"finally",
+];
+
+enum For {
+    In {
+        var: *const Symbol,
+        list: Object,
+    },
+    Range {
+        var: *const Symbol,
+        from: Object,
+        to: Object,
+        by: Option<Object>,
+    },
+}
+
+enum Action {
+    Collect(Object),
+    Sum(Object),
+    Do(Vec<Object>),
+}
+
+struct Loop {
+    fors: Vec<For>,
+    // each action, along with the `when` test guarding it
+    actions: Vec<(Option<Object>, Action)>,
+    finally: Vec<Object>,
+}
+
+// the state of a `For` while the loop runs
+enum Iteration {
+    In {
+        var: *const Symbol,
+        rest: Object,
+    },
+    Range {
+        var: *const Symbol,
+        next: RlispNum,
+        to: RlispNum,
+        by: RlispNum,
+    },
+}
+
+fn is_keyword(obj: Object, keyword: &[u8]) -> bool {
+    if let Some(sym) = <*const Symbol>::maybe_from(obj) {
+        let name: &[u8] = unsafe { &*sym }.as_ref();
+        name == keyword
+    } else {
+        false
+    }
+}
+
+fn is_any_keyword(obj: Object) -> bool {
+    KEYWORDS.iter().any(|keyword| is_keyword(obj, keyword))
+}
+
+fn syntax_error(l: &mut lisp::Lisp, at: Object) -> Object {
+    let kind = Object::from(l.make_symbol(b"loop-syntax-error"));
+    let info = l.list_from_vec(vec![at]);
+    l.alloc(RlispError::custom(kind, info))
+}
+
+fn parse(l: &mut lisp::Lisp, clauses: &[Object]) -> Result<Loop, Object> {
+    let mut parsed = Loop {
+        fors: Vec::new(),
+        actions: Vec::new(),
+        finally: Vec::new(),
+    };
+    let mut i = 0;
+    // returns the clause after `i` and moves past it, or signals a
+    // syntax error at the clause `i` if there isn't one
+    macro_rules! take {
+        () => {{
+            if i + 1 >= clauses.len() {
+                return Err(syntax_error(l, clauses[i]));
+            }
+            i += 1;
+            clauses[i]
+        }};
+    }
+    while i < clauses.len() {
+        let clause = clauses[i];
+        if is_keyword(clause, b"for") {
+            let var = take!();
+            let var = if let Some(var) = <*const Symbol>::maybe_from(var) {
+                var
+            } else {
+                return Err(syntax_error(l, var));
+            };
+            let how = take!();
+            if is_keyword(how, b"in") {
+                let list = take!();
+                parsed.fors.push(For::In { var, list });
+            } else if is_keyword(how, b"from") {
+                let from = take!();
+                if !is_keyword(take!(), b"to") {
+                    return Err(syntax_error(l, clauses[i]));
+                }
+                let to = take!();
+                let by = if i + 1 < clauses.len() && is_keyword(clauses[i + 1], b"by") {
+                    i += 1;
+                    Some(take!())
+                } else {
+                    None
+                };
+                parsed.fors.push(For::Range { var, from, to, by });
+            } else {
+                return Err(syntax_error(l, how));
+            }
+        } else if is_keyword(clause, b"finally") {
+            while i + 1 < clauses.len() && !is_any_keyword(clauses[i + 1]) {
+                i += 1;
+                parsed.finally.push(clauses[i]);
+            }
+        } else {
+            let test = if is_keyword(clause, b"when") {
+                let test = take!();
+                i += 1;
+                if i >= clauses.len() {
+                    return Err(syntax_error(l, clauses[i - 1]));
+                }
+                Some(test)
+            } else {
+                None
+            };
+            let clause = clauses[i];
+            let action = if is_keyword(clause, b"collect") {
+                Action::Collect(take!())
+            } else if is_keyword(clause, b"sum") {
+                Action::Sum(take!())
+            } else if is_keyword(clause, b"do") {
+                let mut forms = Vec::new();
+                while i + 1 < clauses.len() && !is_any_keyword(clauses[i + 1]) {
+                    i += 1;
+                    forms.push(clauses[i]);
+                }
+                Action::Do(forms)
+            } else {
+                return Err(syntax_error(l, clause));
+            };
+            parsed.actions.push((test, action));
+        }
+        i += 1;
+    }
+    Ok(parsed)
+}
+
+fn run(l: &mut lisp::Lisp, parsed: &Loop) -> Object {
+    // The list being collected is built in reverse in a slot on the
+    // stack, so that it survives the gc passes triggered by later
+    // iterations. The lists being iterated over are kept on the stack
+    // as well.
+    let acc_slot = l.stack.len();
+    l.push(Object::nil());
+    let mut iterations = Vec::with_capacity(parsed.fors.len());
+    for clause in &parsed.fors {
+        match *clause {
+            For::In { var, list } => {
+                let list = bubble!(l.evaluate(list)).deref_place();
+                l.push(list);
+                iterations.push(Iteration::In { var, rest: list });
+            }
+            For::Range { var, from, to, by } => {
+                let from = bubble!(l.evaluate(from));
+                let from = into_type_or_error!(l : from => RlispNum);
+                let to = bubble!(l.evaluate(to));
+                let to = into_type_or_error!(l : to => RlispNum);
+                let by = if let Some(by) = by {
+                    let by = bubble!(l.evaluate(by));
+                    let step = into_type_or_error!(l : by => RlispNum);
+                    // a step which doesn't move `next` towards `to`
+                    // would never end the loop
+                    if !(step > RlispNum::from(0)) {
+                        let kind = Object::from(l.make_symbol(b"loop-step-error"));
+                        let info = l.list_from_vec(vec![by.deref_place()]);
+                        return l.alloc(RlispError::custom(kind, info));
+                    }
+                    step
+                } else {
+                    RlispNum::from(1)
+                };
+                iterations.push(Iteration::Range {
+                    var,
+                    next: from,
+                    to,
+                    by,
+                });
+            }
+        }
+    }
+
+    // the loop variables share one scope, which is left for
+    // `evaluate` to end
+    let vars: Vec<_> = iterations
+        .iter()
+        .map(|it| match *it {
+            Iteration::In { var, .. } | Iteration::Range { var, .. } => (var, Object::nil()),
+        })
+        .collect();
+    l.new_scope(&vars);
+
+    let collecting = parsed.actions.iter().any(|&(_, ref action)| match *action {
+        Action::Collect(_) => true,
+        _ => false,
+    });
+    let summing = parsed.actions.iter().any(|&(_, ref action)| match *action {
+        Action::Sum(_) => true,
+        _ => false,
+    });
+    let mut sum = RlispNum::from(0);
+    'iterate: loop {
+        for it in &mut iterations {
+            match *it {
+                Iteration::In { var, ref mut rest } => {
+                    if let Some(&ConsCell { car, cdr, .. }) = <&ConsCell>::maybe_from(*rest) {
+                        l.set_symbol(var, car.deref_place());
+                        *rest = cdr;
+                    } else {
+                        break 'iterate;
+                    }
+                }
+                Iteration::Range {
+                    var,
+                    ref mut next,
+                    ref to,
+                    ref by,
+                } => {
+                    if *next > *to {
+                        break 'iterate;
+                    }
+                    let val = l.alloc_num(next.clone());
+                    l.set_symbol(var, val);
+                    *next += by.clone();
+                }
+            }
+        }
+        for &(test, ref action) in &parsed.actions {
+            if let Some(test) = test {
+                if !bool::from(bubble!(l.evaluate(test))) {
+                    continue;
+                }
+            }
+            match *action {
+                Action::Collect(form) => {
+                    let val = bubble!(l.evaluate(form)).deref_place();
+                    let acc = l.stack[acc_slot];
+                    l.stack[acc_slot] = l.alloc(ConsCell::new(val, acc));
+                }
+                Action::Sum(form) => {
+                    let val = bubble!(l.evaluate(form));
+                    sum += into_type_or_error!(l : val => RlispNum);
+                }
+                Action::Do(ref forms) => {
+                    bubble!(l.eval_forms(forms));
+                }
+            }
+        }
+    }
+    bubble!(l.eval_forms(&parsed.finally));
+
+    if collecting {
+        if let Some(acc) = <&ConsCell>::maybe_from(l.stack[acc_slot]) {
+            l.list_reverse(acc)
+        } else {
+            Object::nil()
+        }
+    } else if summing {
+        l.alloc_num(sum)
+    } else {
+        Object::nil()
+    }
+}
+
+pub fn eval_loop(l: &mut lisp::Lisp, clauses: &[Object]) -> Object {
+    let parsed = match parse(l, clauses) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+    let stack_depth = l.stack.len();
+    let res = run(l, &parsed);
+    let extra = l.stack.len() - stack_depth;
+    l.discard(extra);
+    res
+}
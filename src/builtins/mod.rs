@@ -6,7 +6,11 @@ use lisp;
 use std::boxed::Box;
 use lisp::allocate::AllocObject;
 use lisp::stack_storage::Stack;
+use list::ListOps;
 use types::conversions::*;
+use types::rlisperror::RlispErrorKind;
+
+mod loop_form;
 
 // The macros `special_forms` and `builtin_functions` are the main
 // part of this module. See `make_builtins()` and `make_special_forms`
@@ -34,40 +38,158 @@ pub fn make_special_forms() -> RlispSpecialForms {
         "cond" (&rest clauses) -> {
             for clause in &a {
                 let &ConsCell { car, cdr, .. } = into_type_or_error!(l : *clause => &ConsCell);
-                if bool::from(l.evaluate(car)) {
-                    let &ConsCell { car: cdrcar, .. } =  into_type_or_error!(l : cdr => &ConsCell);
-                    return l.evaluate(cdrcar);
+                let test = bubble!(l.evaluate(car));
+                if bool::from(test) {
+                    let body = try_rlisp_err!(l : l.arg_forms(cdr));
+                    // a clause without a body returns the value of its test
+                    return if body.is_empty() {
+                        test
+                    } else {
+                        l.progn(&body)
+                    };
                 }
             }
             false.into()
         },
         "let" (bindings &rest body) -> {
-            let bindings = a[0];
-            let mut scope = Vec::new();
-
-            #[cfg_attr(feature = "cargo-clippy", allow(explicit_iter_loop))]
-            for binding_pair in into_type_or_error!(l : bindings => &ConsCell).into_iter() {
-                let &ConsCell { car: symbol, cdr, .. } =
-                    into_type_or_error!(l : binding_pair => &ConsCell);
-                let &ConsCell { car: value, .. } =
-                    into_type_or_error!(l : cdr => &ConsCell);
-                scope.push((
-                    into_type_or_error!(l : symbol => *const Symbol),
-                    {
-                        let r = l.evaluate(value);
-                        bubble!(r);
-                        r
-                    }
-                ));
+            let bindings = match let_bindings(l, a[0]) {
+                Ok(bindings) => bindings,
+                Err(e) => return e,
+            };
+
+            // the values are kept on the stack until they are bound,
+            // so that evaluating the later ones can't gc them
+            let stack_depth = l.stack.len();
+            for &(_, value) in &bindings {
+                let r = l.evaluate(value);
+                if r.errorp() {
+                    let extra = l.stack.len() - stack_depth;
+                    l.discard(extra);
+                    return r;
+                }
+                l.push(r);
+            }
+            let values = l.stack.split_off(stack_depth);
+
+            let mut scope = Vec::with_capacity(bindings.len());
+            let mut n_specials = 0;
+            for (&(symbol, _), value) in bindings.iter().zip(values) {
+                if l.special_vars.contains(&symbol) {
+                    l.bind_special(symbol, value);
+                    n_specials += 1;
+                } else {
+                    scope.push((symbol, value));
+                }
             }
             l.new_scope(&scope);
-            let mut res = Object::nil();
-            for body_clause in &a[1..] {
-                res = l.evaluate(*body_clause);
-                bubble!(res);
+            if n_specials == 0 {
+                // the new scope is left for `evaluate` to end after it
+                // evaluates the last body clause in tail position
+                l.progn(&a[1..])
+            } else {
+                // the special variables have to be unbound after the
+                // body returns, so nothing is in tail position
+                let res = l.eval_forms(&a[1..]).deref_place();
+                l.unbind_specials(n_specials);
+                res
+            }
+        },
+        "let*" (bindings &rest body) -> {
+            // like `let`, but each binding gets its own scope, so that
+            // it can see the bindings before it
+            let bindings = match let_bindings(l, a[0]) {
+                Ok(bindings) => bindings,
+                Err(e) => return e,
+            };
+            let mut n_specials = 0;
+            for (symbol, value) in bindings {
+                let value = l.evaluate(value);
+                if value.errorp() {
+                    l.unbind_specials(n_specials);
+                    return value;
+                }
+                if l.special_vars.contains(&symbol) {
+                    l.bind_special(symbol, value);
+                    n_specials += 1;
+                } else {
+                    l.new_scope(&[(symbol, value)]);
+                }
+            }
+            if n_specials == 0 {
+                l.progn(&a[1..])
+            } else {
+                let res = l.eval_forms(&a[1..]).deref_place();
+                l.unbind_specials(n_specials);
+                res
+            }
+        },
+        "progn" (&rest body) -> {
+            l.progn(&a)
+        },
+        "when" (test &rest body) -> {
+            if bool::from(bubble!(l.evaluate(a[0]))) {
+                l.progn(&a[1..])
+            } else {
+                Object::nil()
+            }
+        },
+        "unless" (test &rest body) -> {
+            if bool::from(bubble!(l.evaluate(a[0]))) {
+                Object::nil()
+            } else {
+                l.progn(&a[1..])
             }
-            l.end_scope();
-            res
+        },
+        "and" (&rest forms) -> {
+            // returns `nil` as soon as a form does, or else the value
+            // of the last form
+            if let Some((last, init)) = a.split_last() {
+                for form in init {
+                    if !bool::from(bubble!(l.evaluate(*form))) {
+                        return Object::nil();
+                    }
+                }
+                l.tail_eval(*last)
+            } else {
+                Object::t()
+            }
+        },
+        "or" (&rest forms) -> {
+            // returns the value of the first form which isn't `nil`
+            if let Some((last, init)) = a.split_last() {
+                for form in init {
+                    let res = bubble!(l.evaluate(*form));
+                    if bool::from(res) {
+                        return res;
+                    }
+                }
+                l.tail_eval(*last)
+            } else {
+                Object::nil()
+            }
+        },
+        "case" (keyform &rest clauses) -> {
+            // (case KEYFORM ((KEY...) BODY...) (KEY BODY...) (t BODY...))
+            // `KEY`s are not evaluated, and are compared with `eq`.
+            let key = bubble!(l.evaluate(a[0])).deref_place();
+            let otherwise = Object::from(l.make_symbol(b"otherwise"));
+            let t = Object::from(l.make_symbol(b"t"));
+            for clause in &a[1..] {
+                let &ConsCell { car: keys, cdr: body, .. } =
+                    into_type_or_error!(l : *clause => &ConsCell);
+                let matches = if keys == t || keys == otherwise {
+                    true
+                } else if let Some(keys) = <&ConsCell>::maybe_from(keys) {
+                    keys.into_iter().any(|k| k == key)
+                } else {
+                    keys == key
+                };
+                if matches {
+                    let body = try_rlisp_err!(l : l.arg_forms(body));
+                    return l.progn(&body);
+                }
+            }
+            Object::nil()
         },
         "setf" (place value &rest places values) -> {
             if ::math::oddp(a.len() as _) {
@@ -79,7 +201,12 @@ pub fn make_special_forms() -> RlispSpecialForms {
                 for i in (0..a.len()).step_by(2) {
                     let place = bubble!(l.evaluate(a[i]));
                     let mut place = into_type_or_error!(l : place => Place);
-                    res = bubble!(l.evaluate(a[i + 1]));
+                    // an element place is on the heap, so it is kept
+                    // on the stack while the value is evaluated
+                    l.push(Object::from(place));
+                    res = l.evaluate(a[i + 1]).deref_place();
+                    l.pop();
+                    bubble!(res);
                     *place = res;
                 }
                 res
@@ -123,6 +250,9 @@ pub fn make_special_forms() -> RlispSpecialForms {
                 a[0]
             }
         },
+        "backquote" (template) -> {
+            l.backquote(a[0], 1)
+        },
         "if" (predicate ifclause &rest elseclauses) -> {
             let predicate = a[0];
             let if_clause = a[1];
@@ -132,14 +262,14 @@ pub fn make_special_forms() -> RlispSpecialForms {
                 bubble!(r);
                 r
             }) {
-                l.evaluate(if_clause)
-            } else {
-                let mut res = Object::nil();
-                for clause in else_clauses {
-                    res = l.evaluate(*clause);
-                    bubble!(res);
+                l.tail_eval(if_clause)
+            } else if let Some((last, init)) = else_clauses.split_last() {
+                for clause in init {
+                    bubble!(l.evaluate(*clause));
                 }
-                res
+                l.tail_eval(*last)
+            } else {
+                Object::nil()
             }
         },
         "defun" (name arglist &rest body) -> {
@@ -157,11 +287,34 @@ pub fn make_special_forms() -> RlispSpecialForms {
             l.set_symbol(name_sym, fun);
             fun
         },
+        "defmacro" (name arglist &rest body) -> {
+            let name = a[0];
+            let name_sym = into_type_or_error!(l : name => *const Symbol);
+            let arglist = a[1];
+            let body = &a[2..];
+            let scope = l.symbols.clone();
+            let mac = l.alloc(
+                RlispFunc::from_body(body.into())
+                    .with_name(name)
+                    .with_arglist(arglist)
+                    .with_scope(scope)
+                    .as_macro()
+            );
+            l.set_symbol(name_sym, mac);
+            mac
+        },
         "defvar" (name value) -> {
             let name = into_type_or_error!(l : a[0] => *const Symbol);
-            let val = a[1];
-            let val = l.evaluate(val);
-            bubble!(val);
+            // a `Place` may point into a local namespace, so the global
+            // binding gets the value it points to
+            let val = bubble!(l.evaluate(a[1])).deref_place();
+            l.set_symbol(name, val);
+            val
+        },
+        "defparameter" (name value) -> {
+            let name = into_type_or_error!(l : a[0] => *const Symbol);
+            let val = bubble!(l.evaluate(a[1])).deref_place();
+            l.special_vars.insert(name);
             l.set_symbol(name, val);
             val
         },
@@ -170,23 +323,377 @@ pub fn make_special_forms() -> RlispSpecialForms {
 
             let handlers = &a[1..];
 
+            let depth = l.handlers.len();
+            bubble!(l.catch_errors_named(handlers));
             let res = l.evaluate(statement);
+            l.handlers.truncate(depth);
 
             if let Some(e) = <Object as MaybeInto<&RlispError>>::maybe_into(res) {
+                if e.is_transfer() {
+                    return res;
+                }
                 let e = &e.error;
                 let e = l.error_name(e);
                 for handler in handlers {
                     let &ConsCell { car, cdr, .. } =
                         into_type_or_error!(l : *handler => &ConsCell);
-                    if (car == e) || (car == Object::t()) {
-                        let &ConsCell { car, .. } =
+                    if l.handler_matches(car, e) {
+                        let &ConsCell { car, cdr, .. } =
                             into_type_or_error!(l : cdr => &ConsCell);
+                        // a handler of the form `(error-name (VAR)
+                        // CATCH-FORM)` binds the error to `VAR`
+                        if let Some(&ConsCell { car: form, .. }) = <&ConsCell>::maybe_from(cdr) {
+                            let &ConsCell { car: var, .. } =
+                                into_type_or_error!(l : car => &ConsCell);
+                            let var = into_type_or_error!(l : var => *const Symbol);
+                            l.new_scope(&[(var, res)]);
+                            return l.tail_eval(form);
+                        }
                         return l.evaluate(car);
                     }
                 }
             }
             res
         },
+        "while" (test &rest body) -> {
+            while bool::from(bubble!(l.evaluate(a[0]))) {
+                bubble!(l.eval_forms(&a[1..]));
+            }
+            Object::nil()
+        },
+        "dotimes" (spec &rest body) -> {
+            // (dotimes (VAR COUNT &optional RESULT) BODY...)
+            let spec = try_rlisp_err!(l : l.arg_forms(a[0]));
+            if spec.len() < 2 || spec.len() > 3 {
+                let e = RlispError::bad_args_count((spec.len() as i32).into(),
+                                                   2.into(),
+                                                   3.into());
+                return l.alloc(e);
+            }
+            let var = into_type_or_error!(l : spec[0] => *const Symbol);
+            let count = bubble!(l.evaluate(spec[1]));
+            let count = into_type_or_error!(l : count => i32);
+
+            // `VAR` gets one scope for the whole loop, which is left for
+            // `evaluate` to end after it evaluates `RESULT` in tail
+            // position
+            l.new_scope(&[(var, Object::from(0))]);
+            for i in 0..count {
+                l.set_symbol(var, Object::from(i));
+                bubble!(l.eval_forms(&a[1..]));
+            }
+            l.set_symbol(var, Object::from(count.max(0)));
+            l.progn(&spec[2..])
+        },
+        "dolist" (spec &rest body) -> {
+            // (dolist (VAR LIST &optional RESULT) BODY...)
+            let spec = try_rlisp_err!(l : l.arg_forms(a[0]));
+            if spec.len() < 2 || spec.len() > 3 {
+                let e = RlispError::bad_args_count((spec.len() as i32).into(),
+                                                   2.into(),
+                                                   3.into());
+                return l.alloc(e);
+            }
+            let var = into_type_or_error!(l : spec[0] => *const Symbol);
+            let list = bubble!(l.evaluate(spec[1])).deref_place();
+            if !(list.nilp() || <&ConsCell>::is_type(list)) {
+                let e = RlispError::wrong_type(l.type_name(RlispType::Cons),
+                                               l.type_name(list.what_type()));
+                return l.alloc(e);
+            }
+
+            // `list` stays on the stack so that it isn't gc'd
+            l.push(list);
+            l.new_scope(&[(var, Object::nil())]);
+            let mut rest = list;
+            while let Some(&ConsCell { car, cdr, .. }) = <&ConsCell>::maybe_from(rest) {
+                l.set_symbol(var, car.deref_place());
+                let res = l.eval_forms(&a[1..]);
+                if res.errorp() {
+                    l.pop();
+                    return res;
+                }
+                rest = cdr;
+            }
+            l.pop();
+            if !rest.nilp() {
+                return l.alloc(RlispError::improper_list());
+            }
+            l.set_symbol(var, Object::nil());
+            l.progn(&spec[2..])
+        },
+        "loop" (&rest clauses) -> {
+            loop_form::eval_loop(l, &a)
+        },
+        "unwind-protect" (protected &rest cleanup) -> {
+            let scope_depth = l.symbols.len();
+            let stack_depth = l.stack.len();
+            let res = l.evaluate(a[0]);
+
+            // put the scope and stack back the way they were, even if
+            // `protected` bubbled an error out of the middle of
+            // something, then keep `res` on the stack so that it
+            // survives the cleanup forms
+            l.unwind_scope_to(scope_depth);
+            let extra = l.stack.len().saturating_sub(stack_depth);
+            l.discard(extra);
+            l.push(res);
+            for form in &a[1..] {
+                let cleanup_res = l.evaluate(*form);
+                if cleanup_res.errorp() {
+                    l.pop();
+                    return cleanup_res;
+                }
+            }
+            l.pop()
+        },
+        "handler-case" (expression &rest clauses) -> {
+            let clauses = &a[1..];
+
+            let depth = l.handlers.len();
+            bubble!(l.catch_errors_named(clauses));
+            let res = l.evaluate(a[0]);
+            l.handlers.truncate(depth);
+
+            if let Some(e) = <Object as MaybeInto<&RlispError>>::maybe_into(res) {
+                if e.is_transfer() {
+                    return res;
+                }
+                let name = l.error_name(&e.error);
+                for clause in clauses {
+                    let &ConsCell { car, cdr, .. } =
+                        into_type_or_error!(l : *clause => &ConsCell);
+                    if l.handler_matches(car, name) {
+                        let &ConsCell { car: var, cdr: body, .. } =
+                            into_type_or_error!(l : cdr => &ConsCell);
+                        let mut scope = Vec::new();
+                        if let Some(&ConsCell { car: var, .. }) = <&ConsCell>::maybe_from(var) {
+                            scope.push((into_type_or_error!(l : var => *const Symbol), res));
+                        }
+                        let body = try_rlisp_err!(l : l.arg_forms(body));
+                        l.new_scope(&scope);
+                        return l.progn(&body);
+                    }
+                }
+            }
+            res
+        },
+        "handler-bind" (bindings &rest body) -> {
+            let mut bindings = Vec::new();
+            if let Some(cons) = <&ConsCell>::maybe_from(a[0]) {
+                for binding in cons {
+                    let &ConsCell { car: name, cdr, .. } =
+                        into_type_or_error!(l : binding => &ConsCell);
+                    let &ConsCell { car: function, .. } =
+                        into_type_or_error!(l : cdr => &ConsCell);
+                    bindings.push((name, function));
+                }
+            }
+
+            // the handler functions are kept on the stack so that
+            // they aren't gc'd while the body runs
+            let depth = l.handlers.len();
+            let mut handlers = Vec::with_capacity(bindings.len());
+            for (pushed, &(name, function)) in bindings.iter().enumerate() {
+                let function = l.evaluate(function);
+                if function.errorp() {
+                    l.discard(pushed);
+                    return function;
+                }
+                // a handler named by a variable evaluates to a place
+                let function = function.deref_place();
+                if !<&RlispFunc>::is_type(function) {
+                    l.discard(pushed);
+                    let e = RlispError::wrong_type(l.type_name(RlispType::Function),
+                                                   l.type_name(function.what_type()));
+                    return l.alloc(e);
+                }
+                l.push(function);
+                handlers.push(::lisp::Handler { name, function: Some(function) });
+            }
+            l.handlers.extend(handlers);
+
+            let mut res = Object::nil();
+            for form in &a[1..] {
+                res = l.evaluate(*form);
+                if res.errorp() {
+                    break;
+                }
+            }
+            l.handlers.truncate(depth);
+            l.discard(bindings.len());
+            res
+        },
+        "restart-case" (expression &rest clauses) -> {
+            let clauses = &a[1..];
+            let id = l.next_restart_id;
+            l.next_restart_id += 1;
+
+            let mut restarts = Vec::with_capacity(clauses.len());
+            for clause in clauses {
+                let &ConsCell { car, .. } = into_type_or_error!(l : *clause => &ConsCell);
+                restarts.push((car, id));
+            }
+            let depth = l.restarts.len();
+            l.restarts.extend(restarts);
+            let res = l.evaluate(a[0]);
+            l.restarts.truncate(depth);
+
+            if let Some(&RlispError {
+                error: RlispErrorKind::RestartInvoked { name, args, id: target },
+                ..
+            }) = <Object as MaybeInto<&RlispError>>::maybe_into(res) {
+                if target == id {
+                    for clause in clauses {
+                        let &ConsCell { car, cdr, .. } =
+                            into_type_or_error!(l : *clause => &ConsCell);
+                        if car == name {
+                            let &ConsCell { car: arglist, cdr: body, .. } =
+                                into_type_or_error!(l : cdr => &ConsCell);
+                            let body = try_rlisp_err!(l : l.arg_forms(body));
+                            let args = try_rlisp_err!(l : l.arg_forms(args));
+                            bubble!(l.bind_args(arglist, &args));
+                            return l.progn(&body);
+                        }
+                    }
+                }
+            }
+            res
+        },
+        "block" (name &rest body) -> {
+            let name = into_type_or_error!(l : a[0] => *const Symbol);
+            let id = l.next_block_id;
+            l.next_block_id += 1;
+            let scope_depth = l.symbols.len();
+            let stack_depth = l.stack.len();
+
+            // blocks are lexically scoped, so the block's id is bound
+            // in a new scope, where closures can capture it, to a
+            // symbol that can't be typed
+            let block_sym = l.block_symbol(name);
+            l.new_scope(&[(block_sym, Object::from(id as i32))]);
+            l.blocks.push(id);
+            let mut res = Object::nil();
+            for form in &a[1..] {
+                res = l.evaluate(*form);
+                if res.errorp() {
+                    break;
+                }
+            }
+            l.blocks.pop();
+            l.unwind_scope_to(scope_depth);
+            let extra = l.stack.len().saturating_sub(stack_depth);
+            l.discard(extra);
+
+            if let Some(&RlispError {
+                error: RlispErrorKind::ReturnFrom { value, id: target, .. },
+                ..
+            }) = <Object as MaybeInto<&RlispError>>::maybe_into(res) {
+                if target == id {
+                    return value;
+                }
+            }
+            res
+        },
+        "return-from" (name &optional value) -> {
+            let name = into_type_or_error!(l : a[0] => *const Symbol);
+            let block_sym = l.block_symbol(name);
+            let id = unsafe { l.get_symbol(block_sym) };
+            let id = if let Some(id) = i32::maybe_from(id) {
+                id as usize
+            } else {
+                usize::max_value()
+            };
+            if !l.blocks.contains(&id) {
+                let kind = Object::from(l.make_symbol(b"block"));
+                return l.alloc(RlispError::control_error(kind, Object::from(name)));
+            }
+            let value = if let Some(&value) = a.get(1) {
+                bubble!(l.evaluate(value)).deref_place()
+            } else {
+                Object::nil()
+            };
+            l.alloc(RlispError::return_from(Object::from(name), value, id))
+        },
+        "catch" (tag &rest body) -> {
+            let tag = bubble!(l.evaluate(a[0])).deref_place();
+            let stack_depth = l.stack.len();
+            let scope_depth = l.symbols.len();
+
+            // the tag stays on the stack so that it isn't gc'd
+            l.push(tag);
+            l.catch_tags.push(tag);
+            let mut res = Object::nil();
+            for form in &a[1..] {
+                res = l.evaluate(*form);
+                if res.errorp() {
+                    break;
+                }
+            }
+            l.catch_tags.pop();
+            l.unwind_scope_to(scope_depth);
+            let extra = l.stack.len().saturating_sub(stack_depth);
+            l.discard(extra);
+
+            if let Some(&RlispError {
+                error: RlispErrorKind::Throw { tag: thrown, value },
+                ..
+            }) = <Object as MaybeInto<&RlispError>>::maybe_into(res) {
+                if thrown == tag {
+                    return value;
+                }
+            }
+            res
+        },
+        "destructuring-bind" (pattern value &rest body) -> {
+            // (destructuring-bind ARGLIST VALUE BODY...) binds the
+            // elements of the list `VALUE` as though they were args
+            // passed to a function with the arglist `ARGLIST`
+            let pattern = a[0];
+            let arglist = into_type_or_error!(l : pattern => &ConsCell);
+            let params = try_rlisp_err!(l : l.parse_arglist(arglist));
+            let value = bubble!(l.evaluate(a[1]));
+            let args = match l.destructure(pattern, &params, value) {
+                Ok(args) => args,
+                Err(e) => return e,
+            };
+            bubble!(l.bind_params(&params, pattern, &args));
+            // the new scope is left for `evaluate` to end
+            l.progn(&a[2..])
+        },
+        "flet" (definitions &rest body) -> {
+            // (flet ((NAME ARGLIST BODY...) ...) BODY...)
+            // The functions close over the scope outside of the `flet`,
+            // so they can't call themselves or each other.
+            let definitions = try_rlisp_err!(l : l.arg_forms(a[0]));
+            let mut scope = Vec::with_capacity(definitions.len());
+            for definition in definitions {
+                let &ConsCell { car: name, .. } =
+                    into_type_or_error!(l : definition => &ConsCell);
+                let name = into_type_or_error!(l : name => *const Symbol);
+                scope.push((name, bubble!(local_function(l, definition))));
+            }
+            l.new_scope(&scope);
+            l.progn(&a[1..])
+        },
+        "labels" (definitions &rest body) -> {
+            // like `flet`, but the names are bound in a new scope
+            // before the functions are made, and the functions close
+            // over it, so they can call themselves and each other
+            let definitions = try_rlisp_err!(l : l.arg_forms(a[0]));
+            let mut names = Vec::with_capacity(definitions.len());
+            for definition in &definitions {
+                let &ConsCell { car: name, .. } =
+                    into_type_or_error!(l : *definition => &ConsCell);
+                names.push((into_type_or_error!(l : name => *const Symbol), Object::nil()));
+            }
+            l.new_scope(&names);
+            for (definition, &(name, _)) in definitions.iter().zip(&names) {
+                let fun = bubble!(local_function(l, *definition));
+                l.set_symbol(name, fun);
+            }
+            l.progn(&a[1..])
+        },
         "lambda" (args &rest body) -> {
             let arglist = a[0];
             let body = &a[1..];
@@ -248,7 +755,7 @@ pub fn make_special_forms() -> RlispSpecialForms {
             let symbol = a[1];
             bubble!(symbol);
             let symbol = into_type_or_error!(l : symbol => *const Symbol);
-            let value = a[1];
+            let value = a[2];
             let namespace = l.evaluate(namespace);
             bubble!(namespace);
             let value = l.evaluate(value);
@@ -279,6 +786,93 @@ pub fn make_special_forms() -> RlispSpecialForms {
     }
 }
 
+// reads the bindings `((SYMBOL VALUE) ...)` of a `let` or `let*`
+// into pairs of each symbol and its unevaluated value
+fn let_bindings(
+    l: &mut lisp::Lisp,
+    bindings: Object,
+) -> ::std::result::Result<Vec<(*const Symbol, Object)>, Object> {
+    use evaluator::Evaluator;
+    let bindings = match l.arg_forms(bindings) {
+        Ok(bindings) => bindings,
+        Err(e) => return Err(l.alloc(RlispError::from(e))),
+    };
+    let mut pairs = Vec::with_capacity(bindings.len());
+    for binding in bindings {
+        let pair = match <&ConsCell>::maybe_from(binding) {
+            Some(&ConsCell { car: symbol, cdr, .. }) => {
+                match (<*const Symbol>::maybe_from(symbol), <&ConsCell>::maybe_from(cdr)) {
+                    (Some(symbol), Some(&ConsCell { car: value, .. })) => Some((symbol, value)),
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+        if let Some(pair) = pair {
+            pairs.push(pair);
+        } else {
+            let e = RlispError::wrong_type(l.type_name(RlispType::Cons),
+                                           l.type_name(binding.what_type()));
+            return Err(l.alloc(e));
+        }
+    }
+    Ok(pairs)
+}
+
+// makes the function defined by the `flet` or `labels` definition
+// `(NAME ARGLIST BODY...)`, closing over the current scope
+fn local_function(l: &mut lisp::Lisp, definition: Object) -> Object {
+    use evaluator::Evaluator;
+    let &ConsCell { car: name, cdr, .. } = into_type_or_error!(l : definition => &ConsCell);
+    let &ConsCell { car: arglist, cdr: body, .. } = into_type_or_error!(l : cdr => &ConsCell);
+    if !arglist.nilp() {
+        into_type_or_error!(l : arglist => &ConsCell);
+    }
+    let body = try_rlisp_err!(l : l.arg_forms(body));
+    let scope = l.symbols.clone();
+    l.alloc(
+        RlispFunc::from_body(body)
+            .with_name(name)
+            .with_arglist(arglist)
+            .with_scope(scope)
+    )
+}
+
+/// reads the keyword args `options`, as in `:test eq`, returning the
+/// value passed for each of `keywords`, in order
+pub fn keyword_options(
+    l: &mut lisp::Lisp,
+    options: Object,
+    keywords: &[&[u8]],
+) -> ::std::result::Result<Vec<Option<Object>>, Object> {
+    use evaluator::Evaluator;
+    let options = match l.arg_forms(options.deref_place()) {
+        Ok(options) => options,
+        Err(e) => return Err(l.alloc(RlispError::from(e))),
+    };
+    let keywords: Vec<Object> = keywords
+        .iter()
+        .map(|keyword| Object::from(l.make_symbol(keyword)))
+        .collect();
+    let mut values = vec![None; keywords.len()];
+    for pair in options.chunks(2) {
+        let key = pair[0].deref_place();
+        let i = if let Some(i) = keywords.iter().position(|&k| k == key) {
+            i
+        } else {
+            let allowed = l.list_from_vec(keywords);
+            return Err(l.alloc(RlispError::unknown_keyword(key, allowed)));
+        };
+        if pair.len() < 2 {
+            return Err(l.alloc(RlispError::missing_keyword_value(key)));
+        }
+        if values[i].is_none() {
+            values[i] = Some(pair[1].deref_place());
+        }
+    }
+    Ok(values)
+}
+
 /// Returns the `RlispBuiltinFunctions` used by Rlisp which don't fit
 /// better in `math::make_builtins`. Many of these functions relate to
 /// type-checking, debugging, and basically anything that doesn't
@@ -288,15 +882,23 @@ pub fn make_builtins() -> RlispBuiltins {
         l = lisp;
         "consp" (c) -> { <&ConsCell>::is_type_or_place(c).into() },
         "numberp" (n) -> { RlispNum::is_type_or_place(n).into() },
-        "integerp" (n) -> { i32::is_type_or_place(n).into() },
+        "integerp" (n) -> {
+            (i32::is_type_or_place(n) || <*const RlispBignum>::is_type_or_place(n)).into()
+        },
+        "rationalp" (n) -> { RlispType::Rational.check_type(n).into() },
         "floatp" (n) -> { f64::is_type_or_place(n).into() },
         "symbolp" (s) -> { <&Symbol>::is_type_or_place(s).into() },
         "stringp" (s) -> { <&RlispString>::is_type_or_place(s).into() },
+        "characterp" (c) -> { char::is_type_or_place(c).into() },
         "functionp" (f) -> { <&RlispFunc>::is_type_or_place(f).into() },
         "boolp" (b) -> { bool::is_type_or_place(b).into() },
         "namespacep" (n) -> { <&Namespace>::is_type_or_place(n).into() },
         "placep" (p) -> { Place::is_type(p).into() },
-        "cons" (car cdr) -> { l.alloc(ConsCell::new(car, cdr)) },
+        "vectorp" (v) -> { <&RlispVector>::is_type_or_place(v).into() },
+        "hash-table-p" (h) -> { <&RlispHashTable>::is_type_or_place(h).into() },
+        "cons" (car cdr) -> {
+            l.alloc(ConsCell::new(car.deref_place(), cdr.deref_place()))
+        },
         "list" (&rest items) -> { items },
         "debug" (obj) -> { println!("{:?}", obj); obj },
         "print" (&rest objects) -> {
@@ -312,11 +914,16 @@ pub fn make_builtins() -> RlispBuiltins {
                 false.into()
             }
         },
+        "equal" (first second) -> {
+            Object::from(first.equal(second))
+        },
         "eq" (first &rest objects) -> {
+            // variables evaluate to places, so compare what they refer to
+            let first = first.deref_place();
             if let Some(cons) = <&ConsCell as MaybeFrom<_>>::maybe_from(objects) {
                 #[cfg_attr(feature = "cargo-clippy", allow(explicit_iter_loop))]
                 for el in cons.into_iter() {
-                    if !(first == el) {
+                    if !(first == el.deref_place()) {
                         return false.into();
                     }
                 }
@@ -353,9 +960,145 @@ pub fn make_builtins() -> RlispBuiltins {
         "index-out-of-bounds-error" (idx reciever) -> {
             l.alloc(RlispError::index_out_of_bounds(idx, reciever))
         },
+        "stack-depth-exceeded-error" (max_depth) -> {
+            let _ = into_type_or_error!(l : max_depth => i32);
+            l.alloc(RlispError::stack_depth_exceeded(max_depth))
+        },
         "error" (kind &rest info) -> {
             l.alloc(RlispError::custom(kind, info))
         },
+        "macroexpand-1" (form) -> {
+            use evaluator::Evaluator;
+            if let Some(expansion) = l.macroexpand_1(form) {
+                expansion
+            } else {
+                form
+            }
+        },
+        "macroexpand" (form) -> {
+            use evaluator::Evaluator;
+            l.macroexpand(form)
+        },
+        "funcall" (function &rest args) -> {
+            use evaluator::Evaluator;
+            let args = try_rlisp_err!(l : l.arg_forms(args));
+            l.call_function(function, &args)
+        },
+        "apply" (function &rest args) -> {
+            // the last arg is a list of the rest of the args
+            use evaluator::Evaluator;
+            let mut args = try_rlisp_err!(l : l.arg_forms(args));
+            if let Some(spread) = args.pop() {
+                let spread = spread.deref_place();
+                if !spread.nilp() {
+                    into_type_or_error!(l : spread => &ConsCell);
+                }
+                let spread = try_rlisp_err!(l : l.arg_forms(spread));
+                args.extend(spread.into_iter().map(Object::deref_place));
+            }
+            l.call_function(function, &args)
+        },
+        "eval" (form &optional namespace) -> {
+            use evaluator::Evaluator;
+            let namespace = if namespace.nilp() {
+                None
+            } else {
+                Some(into_type_or_error!(l : namespace => *mut Namespace))
+            };
+            l.eval_in_namespace(form.deref_place(), namespace)
+        },
+        "max-eval-depth" (&optional depth) -> {
+            // returns how deep evaluation may nest before signaling a
+            // `stack-depth-exceeded-error`, first setting it to `depth`
+            if !depth.nilp() {
+                let n = into_type_or_error!(l : depth => i32);
+                if n < 1 {
+                    let kind = Object::from(l.make_symbol(b"eval-depth-error"));
+                    return l.alloc(RlispError::custom(kind, depth.deref_place()));
+                }
+                l.max_eval_depth = n as usize;
+            }
+            Object::from(l.max_eval_depth as i32)
+        },
+        "error-kind" (error) -> {
+            let error = into_type_or_error!(l : error => &RlispError);
+            l.error_name(&error.error)
+        },
+        "error-info" (error) -> {
+            // the args which the error's constructor function takes,
+            // as a list
+            let error = into_type_or_error!(l : error => &RlispError);
+            let info = match error.error {
+                RlispErrorKind::WrongType { wanted, found } => vec![wanted, found],
+                RlispErrorKind::BadArgsCount { min, max, found } => vec![found, min, max],
+                RlispErrorKind::ImproperList => vec![],
+                RlispErrorKind::UnboundSymbol { sym } => vec![sym],
+                RlispErrorKind::RustError(ref e) => {
+                    let message = format!("{}", e);
+                    vec![l.alloc_string(&message)]
+                }
+                RlispErrorKind::NotAType { found } => vec![found],
+                RlispErrorKind::Custom { info, .. } => {
+                    return info;
+                }
+                RlispErrorKind::UndefinedSymbol { sym } => vec![sym],
+                RlispErrorKind::IndexOutOfBounds { idx, reciever } => vec![idx, reciever],
+                RlispErrorKind::StackDepthExceeded { max_depth } => vec![max_depth],
+                RlispErrorKind::ControlError { kind, name } => vec![kind, name],
+                RlispErrorKind::UnknownKeyword { key, allowed } => vec![key, allowed],
+                RlispErrorKind::MissingKeywordValue { key } => vec![key],
+                RlispErrorKind::DestructuringMismatch { pattern, value } => vec![pattern, value],
+                RlispErrorKind::RestartInvoked { name, args, .. } => vec![name, args],
+                RlispErrorKind::ReturnFrom { name, value, .. } => vec![name, value],
+                RlispErrorKind::Throw { tag, value } => vec![tag, value],
+            };
+            l.list_from_vec(info)
+        },
+        "error-message" (error) -> {
+            let error = into_type_or_error!(l : error => &RlispError);
+            let message = format!("{}", error.error);
+            l.alloc_string(&message)
+        },
+        "error-backtrace" (error) -> {
+            // the names of the functions which were being called when
+            // the error was signaled, innermost first
+            let error = into_type_or_error!(l : error => &RlispError);
+            match error.backtrace {
+                Some(ref backtrace) => l.list_from_vec(backtrace.clone()),
+                None => Object::nil(),
+            }
+        },
+        "invoke-restart" (name &rest args) -> {
+            let name = name.deref_place();
+            let id = l.restarts.iter().rev().find(|&&(restart, _)| restart == name);
+            if let Some(&(_, id)) = id {
+                l.alloc(RlispError::restart_invoked(name, args, id))
+            } else {
+                let kind = Object::from(l.make_symbol(b"restart"));
+                l.alloc(RlispError::control_error(kind, name))
+            }
+        },
+        "throw" (tag &optional value) -> {
+            let tag = tag.deref_place();
+            if l.catch_tags.contains(&tag) {
+                l.alloc(RlispError::throw(tag, value.deref_place()))
+            } else {
+                let kind = Object::from(l.make_symbol(b"catch-tag"));
+                l.alloc(RlispError::control_error(kind, tag))
+            }
+        },
+        "control-error" (kind name) -> {
+            l.alloc(RlispError::control_error(kind, name))
+        },
+        "unknown-keyword-error" (key allowed) -> {
+            l.alloc(RlispError::unknown_keyword(key.deref_place(), allowed.deref_place()))
+        },
+        "missing-keyword-value-error" (key) -> {
+            l.alloc(RlispError::missing_keyword_value(key.deref_place()))
+        },
+        "destructuring-error" (pattern value) -> {
+            l.alloc(RlispError::destructuring_mismatch(pattern.deref_place(), value.deref_place()))
+        },
         "global-namespace" () -> {
             Object::from(l.symbols[0])
         },
@@ -387,6 +1130,138 @@ pub fn make_builtins() -> RlispBuiltins {
                 l.alloc(RlispError::index_out_of_bounds(n, list))
             }
         },
+        "make-vector" (&optional length initial) -> {
+            // a vector of `length` elements (by default none), each of
+            // which is `initial`
+            let length = if length.nilp() { 0 } else { into_type_or_error!(l : length => i32) };
+            let elements = vec![initial.deref_place(); length.max(0) as usize];
+            l.alloc(RlispVector::from(elements))
+        },
+        "vector-ref" (vector n) -> {
+            // returns a place, so that `setf` can replace the element
+            let (vector, n) = (vector.deref_place(), n.deref_place());
+            let len = into_type_or_error!(l : vector => &RlispVector).len();
+            let idx = into_type_or_error!(l : n => i32);
+            if idx < 0 || idx as usize >= len {
+                l.alloc(RlispError::index_out_of_bounds(n, vector))
+            } else {
+                l.alloc(ElementPlace::new(vector, n, Object::nil()))
+            }
+        },
+        "vector-push" (vector obj) -> {
+            // adds `obj` to the end of `vector` and returns its index
+            let vec = into_type_or_error!(l : vector => &mut RlispVector);
+            vec.push(obj.deref_place());
+            Object::from((vec.len() - 1) as i32)
+        },
+        "vector-length" (vector) -> {
+            let vec = into_type_or_error!(l : vector => &RlispVector);
+            Object::from(vec.len() as i32)
+        },
+        "vector->list" (vector) -> {
+            let vec = into_type_or_error!(l : vector => &RlispVector);
+            let elements = vec.elements.clone();
+            l.list_from_vec(elements)
+        },
+        "char-code" (c) -> {
+            let c = into_type_or_error!(l : c => char);
+            Object::from(c as i32)
+        },
+        "code-char" (code) -> {
+            // `nil` if `code` isn't a Unicode scalar value
+            let code = into_type_or_error!(l : code => i32);
+            Object::from(::std::char::from_u32(code as u32))
+        },
+        "char-upcase" (c) -> {
+            // chars whose uppercase is more than one char, like `ß`,
+            // are returned unchanged
+            let c = into_type_or_error!(l : c => char);
+            let mut upper = c.to_uppercase();
+            match (upper.next(), upper.next()) {
+                (Some(upper), None) => Object::from(upper),
+                _ => Object::from(c),
+            }
+        },
+        "char" (string n) -> {
+            // the `n`th char of `string`, counting chars rather than
+            // bytes
+            let s = into_type_or_error!(l : string => &RlispString);
+            let idx = into_type_or_error!(l : n => i32);
+            let s: &str = s.as_ref();
+            let c = if idx < 0 { None } else { s.chars().nth(idx as usize) };
+            if let Some(c) = c {
+                Object::from(c)
+            } else {
+                l.alloc(RlispError::index_out_of_bounds(n.deref_place(), string.deref_place()))
+            }
+        },
+        "make-hash-table" (&rest options) -> {
+            // `:test` is `eq` (the default) or `equal`, either quoted
+            // or as the function itself
+            let options = match keyword_options(l, options, &[b":test"]) {
+                Ok(options) => options,
+                Err(e) => return e,
+            };
+            let test = if let Some(test) = options[0] {
+                let eq = l.make_symbol(b"eq");
+                let equal = l.make_symbol(b"equal");
+                if test == Object::from(eq) || test == unsafe { l.get_symbol(eq) } {
+                    HashTest::Eq
+                } else if test == Object::from(equal) || test == unsafe { l.get_symbol(equal) } {
+                    HashTest::Equal
+                } else {
+                    let allowed = l.list_from_vec(vec![Object::from(eq), Object::from(equal)]);
+                    let kind = Object::from(l.make_symbol(b"hash-test-error"));
+                    let info = l.list_from_vec(vec![test, allowed]);
+                    return l.alloc(RlispError::custom(kind, info));
+                }
+            } else {
+                HashTest::Eq
+            };
+            l.alloc(RlispHashTable::new(test))
+        },
+        "gethash" (key table &optional default) -> {
+            // returns a place holding the value stored under `key`, or
+            // `default` if there isn't one, so that `setf` can store
+            // under `key`
+            let table = table.deref_place();
+            into_type_or_error!(l : table => &RlispHashTable);
+            l.alloc(ElementPlace::new(table, key, default))
+        },
+        "remhash" (key table) -> {
+            let table = into_type_or_error!(l : table => &mut RlispHashTable);
+            Object::from(table.remove(key))
+        },
+        "hash-table-count" (table) -> {
+            let table = into_type_or_error!(l : table => &RlispHashTable);
+            Object::from(table.len() as i32)
+        },
+        "maphash" (function table) -> {
+            // calls `function` with each key and value in `table`. The
+            // entries are collected first, and kept on the stack in
+            // case `function` removes them.
+            use evaluator::Evaluator;
+            let (function, table) = (function.deref_place(), table.deref_place());
+            let entries = into_type_or_error!(l : table => &RlispHashTable).entries();
+            let stack_depth = l.stack.len();
+            l.push(function);
+            l.push(table);
+            for &(key, val) in &entries {
+                l.push(key);
+                l.push(val);
+            }
+            let mut res = Object::nil();
+            for (key, val) in entries {
+                let called = l.call_function(function, &[key, val]);
+                if called.errorp() {
+                    res = called;
+                    break;
+                }
+            }
+            let extra = l.stack.len() - stack_depth;
+            l.discard(extra);
+            res
+        },
     }
 }
 
@@ -13,47 +13,323 @@ use builtins::{RlispBuiltinFunc, RlispSpecialForm};
 use gc;
 use types::conversions::*;
 use symbols_table::SymbolLookup;
+use lisp::{Handler, SourceTracking};
 
 pub trait Evaluator
     : SymbolLookup + lisp::stack_storage::Stack + gc::GarbageCollector + list::ListOps
-    {
+    + SourceTracking {
     fn evaluate(&mut self, input: Object) -> Object {
         info!("evaluating {}", input);
+        if *self.eval_depth() >= self.max_eval_depth() {
+            let max_depth = Object::from(self.max_eval_depth() as i32);
+            return self.alloc(RlispError::stack_depth_exceeded(max_depth));
+        }
+        *self.eval_depth() += 1;
         debug!(
             "evaluate(): pushing {} to the stack so it doesn't get gc'd",
             input
         );
         self.push(input); // push `input` to the stack so that the gc doesn't get rid of it
-        let res = match input.what_type() {
-            RlispType::Sym => {
-                Object::from(unsafe { self.sym_ref(<*const Symbol>::from_unchecked(input)) })
+
+        // Forms in tail position (see `tail_eval`) are evaluated by
+        // looping here instead of by recursing, so that they don't
+        // grow the Rust stack. The scopes they push are left in place
+        // until the loop exits, and are then discarded by returning
+        // to `scope_depth`. The same goes for the frames they push to
+        // the call stack.
+        let scope_depth = self.scope().len();
+        let frame_depth = self.call_stack().len();
+        let mut form = input;
+        let mut res = loop {
+            let res = match form.what_type() {
+                RlispType::Sym => {
+                    let sym = unsafe { <*const Symbol>::from_unchecked(form) };
+                    let name: &[u8] = unsafe { &*sym }.as_ref();
+                    if name == b"t" {
+                        Object::t()
+                    } else if name.first() == Some(&b':') {
+                        // keywords evaluate to themselves
+                        form
+                    } else {
+                        Object::from(self.sym_ref(sym))
+                    }
+                }
+                RlispType::Cons => {
+                    let cons = unsafe { <&ConsCell>::from_unchecked(form) };
+                    self.eval_list(cons, scope_depth, frame_depth)
+                }
+                RlispType::Place => self.evaluate(*unsafe { Place::from_unchecked(form) }),
+                RlispType::Number
+                | RlispType::Float
+                | RlispType::Integer
+                | RlispType::Rational
+                | RlispType::Char
+                | RlispType::Bool
+                | RlispType::String
+                | RlispType::Function
+                | RlispType::Error
+                | RlispType::Namespace
+                | RlispType::Vector
+                | RlispType::HashTable
+                | RlispType::StringBuilder
+                | RlispType::Bignum
+                | RlispType::Ratio => form,
+            };
+            if let Some(next) = self.pending_tail_form().take() {
+                debug!("evaluate(): continuing with {} in tail position", next);
+                self.pop();
+                self.push(next);
+                form = next;
+            } else {
+                break res;
             }
-            RlispType::Cons => self.eval_list(unsafe { <&ConsCell>::from_unchecked(input) }),
-            RlispType::Place => self.evaluate(*unsafe { Place::from_unchecked(input) }),
-            RlispType::Number
-            | RlispType::Float
-            | RlispType::Integer
-            | RlispType::Bool
-            | RlispType::String
-            | RlispType::Function
-            | RlispType::Error
-            | RlispType::Namespace => input,
         };
+        if res.errorp() && self.record_error_origin(res, form) {
+            res = self.signal(res);
+        }
+        self.call_stack().truncate(frame_depth);
+        self.unwind_scope_to(scope_depth);
+        // `res` goes on the stack too, so that a freshly allocated
+        // result survives the gc pass
+        self.push(res);
         self.gc_maybe_pass();
+        self.pop();
         info!("{} evaluated to {}", input, res);
         let _popped = self.pop();
         debug!(
             "evaluate(): popped {} from the stack as we have finished evaluating it",
             _popped
         );
-        debug_assert!(_popped == input);
+        debug_assert!(_popped == form);
+        *self.eval_depth() -= 1;
+        res
+    }
+    fn tail_eval(&mut self, form: Object) -> Object {
+        // Special forms call this on a form in tail position instead
+        // of `evaluate`ing it, and return the result. The form is
+        // then evaluated by the `evaluate` loop which called the
+        // special form, along with any scope the special form left
+        // pushed.
+        debug_assert!(self.pending_tail_form().is_none());
+        *self.pending_tail_form() = Some(form);
+        Object::nil()
+    }
+    fn record_error_origin(&mut self, error: Object, form: Object) -> bool {
+        // The first `evaluate` loop to see an error is the innermost
+        // one, so the call stack still holds every function which
+        // was active when it was signaled. Outer loops leave the
+        // recorded backtrace alone. The location is that of the
+        // innermost `form` which was read from a source file, so
+        // outer loops fill it in if inner ones couldn't. Returns true
+        // if this is the first loop to see `error`, which should then
+        // signal it.
+        let error = unsafe { <&mut RlispError>::from_unchecked(error) };
+        if error.is_transfer() {
+            return false;
+        }
+        let first_seen = error.backtrace.is_none();
+        if first_seen {
+            let mut backtrace = self.call_stack().clone();
+            backtrace.reverse();
+            error.backtrace = Some(backtrace);
+        }
+        if error.location.is_none() {
+            error.location = self.source_span(form);
+        }
+        first_seen
+    }
+    fn handler_matches(&mut self, wanted: Object, error_name: Object) -> bool {
+        // `t` names a handler for every error
+        wanted == error_name || wanted == Object::t() || wanted == Object::from(self.make_symbol(b"t"))
+    }
+    fn signal(&mut self, error: Object) -> Object {
+        // Call the handlers established by `handler-bind` for
+        // `error`, innermost first, while everything between them
+        // and the point where `error` was signaled is still in
+        // place. A handler declines by returning normally; if it
+        // returns an error instead, e.g. by calling `invoke-restart`,
+        // that error replaces `error`. The search stops at the first
+        // `handler-case` or `catch-error` which will catch `error`
+        // once it has bubbled up that far.
+        let name = {
+            let e = unsafe { <&RlispError>::from_unchecked(error) };
+            self.error_name(&e.error)
+        };
+        let mut i = self.handlers().len();
+        while i > 0 {
+            i -= 1;
+            let Handler { name: wanted, function } = self.handlers()[i];
+            if !self.handler_matches(wanted, name) {
+                continue;
+            }
+            let function = if let Some(function) = function {
+                function
+            } else {
+                break;
+            };
+            // a handler runs with only the handlers that were
+            // established outside of it
+            let inner = self.handlers().split_off(i);
+            let res = self.call_handler(function, error);
+            self.handlers().extend(inner);
+            if res.errorp() {
+                return res;
+            }
+        }
+        error
+    }
+    fn call_handler(&mut self, handler: Object, error: Object) -> Object {
+        // `error` is passed to `handler` through a place, so that it
+        // gets bound like any other arg instead of bubbling out of
+        // the arglist. It stays on the stack so that it isn't gc'd.
+        let func = into_type_or_error!(self : handler => &mut RlispFunc);
+        let mut slot = Box::new(error);
+        self.push(error);
+        self.push(Object::from(Place::from(&mut *slot)));
+        self.push(Object::from(1));
+        let res = self.put_function_scope_and_call(func);
+        self.pop();
+        res
+    }
+    fn call_function(&mut self, func: Object, args: &[Object]) -> Object {
+        // Call the function `func` with the already-evaluated `args`,
+        // as `funcall` and `apply` do. `func` may also be a symbol
+        // naming a function. `func` is kept on the stack during the
+        // call so that it isn't gc'd.
+        let func = if let Some(sym) = <*const Symbol>::maybe_from(func) {
+            bubble!(unsafe { self.get_symbol(sym) })
+        } else {
+            func.deref_place()
+        };
+        let f = into_type_or_error!(self : func => &mut RlispFunc);
+        let special_form = match f.body {
+            FunctionBody::SpecialForm(_) => true,
+            _ => false,
+        };
+        if special_form || f.is_macro() {
+            // special forms and macros take their args unevaluated,
+            // so calling them this way doesn't make sense
+            let wanted = Object::from(self.make_symbol(b"function"));
+            let found = Object::from(self.make_symbol(if f.is_macro() {
+                b"macro"
+            } else {
+                b"special-form"
+            }));
+            return self.alloc(RlispError::wrong_type(wanted, found));
+        }
+        self.push(func);
+        for arg in args.iter().rev() {
+            self.push(*arg);
+        }
+        self.push(Object::from(args.len() as i32));
+        let res = self.put_function_scope_and_call(f);
+        self.pop();
         res
     }
-    fn eval_list(&mut self, c: *const ConsCell) -> Object {
+    fn eval_in_namespace(&mut self, form: Object, namespace: Option<*mut Namespace>) -> Object {
+        // Evaluate `form`, as `eval` does, in a scope holding only the
+        // global namespace and `namespace`, if it is given, instead of
+        // the current one. The current scope is set aside meanwhile,
+        // with its namespaces kept on the stack so that they aren't
+        // gc'd.
+        let mut scope = vec![self.scope()[0]];
+        scope.extend(namespace);
+        let saved = ::std::mem::replace(self.scope_mut(), scope);
+        for nmspc in &saved {
+            self.push(Object::from(*nmspc as *const Namespace));
+        }
+        self.push(form);
+        let res = self.evaluate(form);
+        self.discard(saved.len() + 1);
+        *self.scope_mut() = saved;
+        res
+    }
+    fn catch_errors_named(&mut self, clauses: &[Object]) -> Object {
+        // Establish a `Handler` without a function for the car of
+        // each of `clauses`, as `handler-case` and `catch-error` do,
+        // so that `signal` leaves errors with those names for them.
+        let mut handlers = Vec::with_capacity(clauses.len());
+        for clause in clauses {
+            let &ConsCell { car, .. } = into_type_or_error!(self : *clause => &ConsCell);
+            handlers.push(Handler {
+                name: car,
+                function: None,
+            });
+        }
+        self.handlers().extend(handlers);
+        Object::nil()
+    }
+    fn bind_args(&mut self, arglist: Object, args: &[Object]) -> Object {
+        // Bind `args` to the symbols in `arglist` in a new scope, as
+        // though they had been passed to a function with that
+        // arglist. The scope is left for the caller to end.
+        let n_args = args.len() as i32;
+        let arglist = if let Some(arglist) = <&ConsCell>::maybe_from(arglist) {
+            arglist
+        } else if n_args == 0 {
+            self.new_scope(&[]);
+            return Object::nil();
+        } else {
+            let e = RlispError::bad_args_count(n_args.into(), 0.into(), 0.into());
+            return self.alloc(e);
+        };
+        if !try_rlisp_err!(self : self.arglist_compat(arglist, n_args)) {
+            let (min_args, max_args) = try_rlisp_err!(self : self.acceptable_range(arglist));
+            let e = RlispError::bad_args_count(n_args.into(), min_args.into(), max_args.into());
+            return self.alloc(e);
+        }
+        for arg in args.iter().rev() {
+            self.push(*arg);
+        }
+        self.get_args_for_lisp_func(arglist, n_args)
+    }
+    fn bind_special(&mut self, sym: *const Symbol, val: Object) {
+        // Dynamically bind the special variable `sym` to `val` by
+        // replacing its global value until `unbind_specials` is
+        // called. The symbol and its old value are pushed onto the
+        // stack, which keeps the old value from being gc'd meanwhile.
+        let mut place = self.global_symbol_tab().sym_ref(sym);
+        self.push(Object::from(sym));
+        self.push(*place);
+        *place = val.deref_place();
+    }
+    fn unbind_specials(&mut self, n: usize) {
+        // undo the last `n` calls to `bind_special`
+        for _ in 0..n {
+            let old = self.pop();
+            let sym = self.pop();
+            let sym = unsafe { <*const Symbol>::from_unchecked(sym) };
+            let mut place = self.global_symbol_tab().sym_ref(sym);
+            *place = old;
+        }
+    }
+    fn eval_forms(&mut self, forms: &[Object]) -> Object {
+        // evaluate each of `forms` in order, none in tail position,
+        // stopping at the first error. Returns the value of the last.
+        let mut res = Object::nil();
+        for form in forms {
+            res = bubble!(self.evaluate(*form));
+        }
+        res
+    }
+    fn progn(&mut self, body: &[Object]) -> Object {
+        // evaluate each of `body` in order, leaving the last in tail
+        // position
+        if let Some((last, init)) = body.split_last() {
+            for form in init {
+                bubble!(self.evaluate(*form));
+            }
+            self.tail_eval(*last)
+        } else {
+            Object::nil()
+        }
+    }
+    fn eval_list(&mut self, c: *const ConsCell, scope_depth: usize, frame_depth: usize) -> Object {
         // Evaluating a list entails treating the car as a function
         // and calling it with the rest of the list as arguments.
-        // Future improvement: push NumArgs to the stack to allow for
-        // &optional and &rest args
+        // `scope_depth` and `frame_depth` are those of the `evaluate`
+        // loop which called this method; the list is always in tail
+        // position relative to it.
 
         let &ConsCell { car, cdr, .. } = unsafe { &(*c) };
         let car = self.evaluate(car);
@@ -62,28 +338,18 @@ pub trait Evaluator
 
         let func = into_type_or_error!(self : car => &mut RlispFunc);
 
+        if func.is_macro() {
+            let expansion = bubble!(self.call_macro(func, cdr));
+            return self.tail_eval(expansion);
+        }
+
         if let FunctionBody::SpecialForm(ref mut funcb) = func.body {
-            let num_args = if let Some(cons) = cdr.maybe_into() {
-                let mut iter = list::iter(unsafe { self.list_reverse(cons).into_unchecked() });
-                let mut num_args: i32 = 0;
-                loop {
-                    let res = iter.improper_next();
-                    if let list::ConsIteratorResult::Final(Some(_)) = res {
-                        let e: Error = ErrorKind::ImproperList.into();
-                        let e: RlispError = e.into();
-                        return self.alloc(e);
-                    } else if let list::ConsIteratorResult::More(obj) = res {
-                        num_args += 1;
-                        debug!("eval_list(): pushing {} as an argument", obj);
-                        self.push(obj);
-                    } else {
-                        break;
-                    }
-                }
-                num_args
-            } else {
-                0
-            };
+            let forms = try_rlisp_err!(self : self.arg_forms(cdr));
+            for obj in forms.iter().rev() {
+                debug!("eval_list(): pushing {} as an argument", obj);
+                self.push(*obj);
+            }
+            let num_args = forms.len() as i32;
 
             if let Some(arglist) = func.arglist {
                 if arglist.nilp() && num_args == 0 {
@@ -93,6 +359,7 @@ pub trait Evaluator
                     if self.arglist_compat(arglist, num_args).unwrap() {
                         self.call_special_form((*funcb).as_mut(), num_args)
                     } else {
+                        self.discard(num_args as _);
                         let (min_args, max_args) = self.acceptable_range(arglist).unwrap();
                         self.alloc(RlispError::bad_args_count(
                             num_args.into(),
@@ -105,36 +372,190 @@ pub trait Evaluator
                 panic!("special forms must have arglists!");
             }
         } else {
-            let num_args = if let Some(cons) = cdr.maybe_into() {
-                let mut iter = list::iter(unsafe { self.list_reverse(cons).into_unchecked() });
-                let mut num_args: i32 = 0;
-                loop {
-                    let res = iter.improper_next();
-                    if let list::ConsIteratorResult::Final(Some(_)) = res {
-                        let e: Error = ErrorKind::ImproperList.into();
-                        let e: RlispError = e.into();
-                        return self.alloc(e);
-                    } else if let list::ConsIteratorResult::More(obj) = res {
-                        let obj = self.evaluate(obj);
-                        num_args += 1;
-                        debug!("eval_list(): pushing {} as an argument", obj);
-                        self.push(obj);
-                    } else {
-                        break;
+            // args are evaluated last-to-first, so that the first arg
+            // ends up on top of the stack
+            let forms = try_rlisp_err!(self : self.arg_forms(cdr));
+            for (pushed, obj) in forms.iter().rev().enumerate() {
+                let obj = self.evaluate(*obj);
+                if obj.errorp() {
+                    // leave the stack as we found it
+                    self.discard(pushed);
+                    return obj;
+                }
+                debug!("eval_list(): pushing {} as an argument", obj);
+                self.push(obj);
+            }
+            let num_args = forms.len() as i32;
+
+            debug!("eval_list(): pushing {} as num_args", num_args);
+            self.push(Object::from(num_args as i32));
+            self.put_function_scope_and_tail_call(func, scope_depth, frame_depth)
+        }
+    }
+    fn arg_forms(&self, args: Object) -> Result<Vec<Object>> {
+        // Collect the elements of the list `args` so that they can be
+        // pushed to the stack in reverse order. This avoids
+        // allocating a reversed copy of `args`, which nothing would
+        // keep from being gc'd while the args are evaluated.
+        let mut forms = Vec::new();
+        if let Some(cons) = <&ConsCell>::maybe_from(args) {
+            let mut iter = list::iter(cons);
+            loop {
+                match iter.improper_next() {
+                    ConsIteratorResult::More(obj) => forms.push(obj),
+                    ConsIteratorResult::Final(None) => break,
+                    ConsIteratorResult::Final(Some(_)) => {
+                        return Err(ErrorKind::ImproperList.into());
                     }
                 }
-                num_args
+            }
+        }
+        Ok(forms)
+    }
+    fn call_macro(&mut self, func: &mut RlispFunc, args: Object) -> Object {
+        // Macros are called like any other `LispFn`, except that
+        // their args are pushed unevaluated, like those of a special
+        // form. The return value is the expansion, which the caller
+        // is responsible for evaluating.
+        let forms = try_rlisp_err!(self : self.arg_forms(args));
+        for obj in forms.iter().rev() {
+            debug!("call_macro(): pushing {} as an argument", obj);
+            self.push(*obj);
+        }
+        let num_args = forms.len() as i32;
+        debug!("call_macro(): pushing {} as num_args", num_args);
+        self.push(Object::from(num_args));
+        self.put_function_scope_and_call(func)
+    }
+    fn macroexpand_1(&mut self, form: Object) -> Option<Object> {
+        // If `form` is a list whose car names a macro, return the
+        // result of expanding it once. Otherwise, return `None`.
+        let &ConsCell { car, cdr, .. } = <&ConsCell>::maybe_from(form)?;
+        let sym = <*const Symbol>::maybe_from(car)?;
+        let func = unsafe { self.get_symbol(sym) };
+        let func = <&mut RlispFunc>::maybe_from(func)?;
+        if func.is_macro() {
+            Some(self.call_macro(func, cdr))
+        } else {
+            None
+        }
+    }
+    fn macroexpand(&mut self, mut form: Object) -> Object {
+        // expand `form` repeatedly until its car no longer names a
+        // macro
+        while let Some(expansion) = self.macroexpand_1(form) {
+            form = bubble!(expansion);
+        }
+        form
+    }
+    fn backquote(&mut self, form: Object, depth: i32) -> Object {
+        // Build the object described by the template `form`, which
+        // is nested inside `depth` levels of `backquote`. Forms
+        // wrapped in `comma` at depth 1 are evaluated, and the
+        // elements of lists returned by `comma-at` at depth 1 are
+        // spliced into their enclosing list. Deeper `comma`s are
+        // left in place, with their contents processed one level
+        // shallower.
+        let cons = if let Some(cons) = <&ConsCell>::maybe_from(form) {
+            cons
+        } else {
+            return form;
+        };
+        let comma = Object::from(self.make_symbol(b"comma"));
+        let comma_at = Object::from(self.make_symbol(b"comma-at"));
+        let backquote = Object::from(self.make_symbol(b"backquote"));
+
+        if cons.car == comma || cons.car == backquote {
+            let &ConsCell { car: arg, .. } = into_type_or_error!(self : cons.cdr => &ConsCell);
+            return if cons.car == comma && depth == 1 {
+                let val = bubble!(self.evaluate(arg));
+                if let Some(place) = Place::maybe_from(val) {
+                    *place
+                } else {
+                    val
+                }
             } else {
-                0
+                let inner_depth = if cons.car == comma {
+                    depth - 1
+                } else {
+                    depth + 1
+                };
+                let inner = bubble!(self.backquote(arg, inner_depth));
+                self.list_from_vec(vec![cons.car, inner])
             };
+        }
 
-            debug!("eval_list(): pushing {} as num_args", num_args);
-            self.push(Object::from(num_args as i32));
-            self.put_function_scope_and_call(func)
+        // The list is accumulated in reverse in `acc`, which is kept
+        // on the stack so that it survives any gc passes triggered by
+        // evaluating later elements.
+        let mut acc = Object::nil();
+        self.push(acc);
+        let mut iter = list::iter(cons);
+        loop {
+            let el = match iter.improper_next() {
+                ConsIteratorResult::More(el) => el,
+                ConsIteratorResult::Final(None) => break,
+                ConsIteratorResult::Final(Some(_)) => {
+                    self.pop();
+                    return self.alloc(RlispError::improper_list());
+                }
+            };
+            let spliced = <&ConsCell>::maybe_from(el).and_then(|el| {
+                if el.car == comma_at {
+                    <&ConsCell>::maybe_from(el.cdr).map(|cdr| cdr.car)
+                } else {
+                    None
+                }
+            });
+            let built = match spliced {
+                Some(arg) if depth == 1 => self.evaluate(arg),
+                Some(arg) => {
+                    let inner = self.backquote(arg, depth - 1);
+                    if inner.errorp() {
+                        inner
+                    } else {
+                        self.list_from_vec(vec![comma_at, inner])
+                    }
+                }
+                None => self.backquote(el, depth),
+            };
+            if built.errorp() {
+                self.pop();
+                return built;
+            }
+            if spliced.is_some() && depth == 1 {
+                if let Some(to_splice) = <&ConsCell>::maybe_from(built) {
+                    for el in to_splice {
+                        acc = self.alloc(ConsCell::new(el, acc));
+                    }
+                } else if !built.nilp() {
+                    self.pop();
+                    let e = RlispError::wrong_type(
+                        self.type_name(RlispType::Cons),
+                        self.type_name(built.what_type()),
+                    );
+                    return self.alloc(e);
+                }
+            } else {
+                acc = self.alloc(ConsCell::new(built, acc));
+            }
+            self.pop();
+            self.push(acc);
+        }
+        self.pop();
+        if let Some(acc) = <&ConsCell>::maybe_from(acc) {
+            self.list_reverse(acc)
+        } else {
+            acc
         }
     }
     fn call_special_form(&mut self, func: &mut RlispSpecialForm, n_args: i32) -> Object;
     fn call_rust_func(&mut self, func: &mut RlispBuiltinFunc, n_args: i32) -> Object;
+    fn pending_tail_form(&mut self) -> &mut Option<Object>;
+    fn call_stack(&mut self) -> &mut Vec<Object>;
+    fn handlers(&mut self) -> &mut Vec<Handler>;
+    fn eval_depth(&mut self) -> &mut usize;
+    fn max_eval_depth(&self) -> usize;
     // These methods are left up to the implementor because
     // `RlispBuiltinFunc`s take an &mut lisp::Lisp, which is not the
     // same as taking an &mut Self
@@ -151,15 +572,39 @@ pub trait Evaluator
         func: &mut RlispFunc,
         arglist: Option<&ConsCell>,
         n_args: i32,
+        in_tail_position: bool,
     ) -> Object {
         match func.body {
             FunctionBody::LispFn(ref funcb) => {
                 if let Some(arglist) = arglist {
                     bubble!(self.get_args_for_lisp_func(arglist, n_args));
                 }
+                if in_tail_position {
+                    // The arg scope is left for `evaluate` to
+                    // discard, and `func` is kept on the stack so
+                    // that its body survives until we're done with
+                    // it.
+                    if let Some((last, init)) = funcb.split_last() {
+                        self.push(Object::from(func as *const RlispFunc));
+                        for line in init {
+                            let res = self.evaluate(*line);
+                            if res.errorp() {
+                                self.pop();
+                                return res;
+                            }
+                        }
+                        self.pop();
+                        return self.tail_eval(*last);
+                    } else {
+                        return Object::nil();
+                    }
+                }
                 let mut ret = Object::nil();
                 for line in funcb {
                     ret = self.evaluate(*line);
+                    if ret.errorp() {
+                        break;
+                    }
                 }
                 if arglist.is_some() {
                     self.pop_args_from_lisp_func();
@@ -171,19 +616,44 @@ pub trait Evaluator
         }
     }
     fn put_function_scope_and_call(&mut self, func: &mut RlispFunc) -> Object {
+        let frame_depth = self.call_stack().len();
+        self.call_stack().push(func.name().unwrap_or_else(Object::nil));
         if let Some(ref scope) = func.scope {
             for nmspc in scope {
                 self.push_namespace(*nmspc);
             }
         }
-        let res = self.funcall(func);
+        let res = self.funcall(func, false);
         if let Some(ref scope) = func.scope {
             for _ in scope {
                 self.end_scope();
             }
         }
+        self.call_stack().truncate(frame_depth);
         res
     }
+    fn put_function_scope_and_tail_call(
+        &mut self,
+        func: &mut RlispFunc,
+        scope_depth: usize,
+        frame_depth: usize,
+    ) -> Object {
+        // Like `put_function_scope_and_call`, but for calls in tail
+        // position. Once the args are evaluated, the scopes and call
+        // stack frames pushed by the forms enclosing this call are no
+        // longer needed, so they are discarded before `func`'s are
+        // pushed. `func`'s are then left in place for `evaluate` to
+        // discard.
+        self.unwind_scope_to(scope_depth);
+        self.call_stack().truncate(frame_depth);
+        self.call_stack().push(func.name().unwrap_or_else(Object::nil));
+        if let Some(ref scope) = func.scope {
+            for nmspc in scope {
+                self.push_namespace(*nmspc);
+            }
+        }
+        self.funcall(func, true)
+    }
     fn funcall_unchecked(&mut self, func: &mut RlispFunc, n_args: i32) -> Object {
         warn!("Calling a function without checking args!");
         if let FunctionBody::RustFn(ref mut funcb) = func.body {
@@ -194,7 +664,7 @@ pub trait Evaluator
             self.alloc(e)
         }
     }
-    fn funcall(&mut self, func: &mut RlispFunc) -> Object {
+    fn funcall(&mut self, func: &mut RlispFunc, in_tail_position: bool) -> Object {
         debug!("calling function {:?}", func);
         let n_args = pop_bubble!(self);
         let n_args: i32 = unsafe { n_args.into_unchecked() };
@@ -205,25 +675,27 @@ pub trait Evaluator
                                       self.arglist_compat(arglist, n_args))
                 {
                     debug!("#{} is compatible with the arglist {}", n_args, arglist);
-                    self.funcall_after_check(func, Some(arglist), n_args)
+                    self.funcall_after_check(func, Some(arglist), n_args, in_tail_position)
                 } else {
                     debug!(
                         "#{} is not compatible with the arglist {:?}",
                         n_args, arglist
                     );
+                    self.discard(n_args as _);
                     let (min_args, max_args) = try_rlisp_err!(self :
                                            self.acceptable_range(arglist));
-                    let e: Error = ErrorKind::WrongArgsCount(n_args, min_args, max_args).into();
-                    let e: RlispError = e.into();
+                    let e = RlispError::bad_args_count(n_args.into(),
+                                                       min_args.into(),
+                                                       max_args.into());
                     self.alloc(e)
                 }
             } else {
                 debug_assert!(arglist == Object::nil());
                 if n_args == 0 {
-                    self.funcall_after_check(func, None, n_args)
+                    self.funcall_after_check(func, None, n_args, in_tail_position)
                 } else {
-                    let e: Error = ErrorKind::WrongArgsCount(n_args, 0, Some(0)).into();
-                    let e: RlispError = e.into();
+                    self.discard(n_args as _);
+                    let e = RlispError::bad_args_count(n_args.into(), 0.into(), 0.into());
                     self.alloc(e)
                 }
             }
@@ -232,118 +704,260 @@ pub trait Evaluator
         }
     }
 
-    fn acceptable_range(&mut self, arglist: &ConsCell) -> Result<(i32, Option<i32>)> {
-        use types::function::ArgType;
-        debug!("Checking acceptable range for arglist {}", arglist);
+    fn parse_arglist(&mut self, arglist: &ConsCell) -> Result<Vec<Param>> {
+        // Read an arglist into its parameters. The lambda list
+        // keywords `&optional`, `&rest` and `&key` are not included.
+        let symbol = |obj: Object| {
+            <*const Symbol>::maybe_from(obj)
+                .ok_or_else(|| Error::from(ErrorKind::WrongType(RlispType::Sym, obj.what_type())))
+        };
         let mut iter = list::iter(arglist);
-        let mut min_args: i32 = 0;
-        let mut max_args: Option<i32> = Some(0);
+        let mut params = Vec::new();
         let mut arg_type = ArgType::Mandatory;
         loop {
-            let res = iter.improper_next();
-            if let list::ConsIteratorResult::Final(Some(_)) = res {
-                return Err(ErrorKind::ImproperList.into());
-            } else if let list::ConsIteratorResult::More(obj) = res {
-                let sym = <*const Symbol as MaybeFrom<Object>>::maybe_from(obj).unwrap();
-                let arg_name: &[u8] = unsafe { &*sym }.as_ref();
-                if arg_name == b"&optional" {
-                    arg_type = ArgType::Optional;
-                } else if arg_name == b"&rest" {
-                    arg_type = ArgType::Rest;
-                } else {
-                    match arg_type {
-                        ArgType::Mandatory => {
-                            min_args += 1;
-                            if let Some(ref mut ma) = max_args {
-                                *ma += 1;
-                            }
+            match iter.improper_next() {
+                ConsIteratorResult::Final(None) => break,
+                ConsIteratorResult::Final(Some(_)) => {
+                    return Err(ErrorKind::ImproperList.into());
+                }
+                ConsIteratorResult::More(obj) => {
+                    if let Some(sym) = <*const Symbol>::maybe_from(obj) {
+                        let arg_name: &[u8] = unsafe { &*sym }.as_ref();
+                        if arg_name == b"&optional" {
+                            arg_type = ArgType::Optional;
+                        } else if arg_name == b"&rest" {
+                            arg_type = ArgType::Rest;
+                        } else if arg_name == b"&key" {
+                            arg_type = ArgType::Key;
+                        } else {
+                            params.push(Param {
+                                var: ParamVar::Symbol(sym),
+                                arg_type,
+                                default: None,
+                                supplied_p: None,
+                            });
                         }
-                        ArgType::Optional => {
-                            if let Some(ref mut ma) = max_args {
-                                *ma += 1;
+                    } else if let Some(spec) = <&ConsCell>::maybe_from(obj) {
+                        match arg_type {
+                            ArgType::Mandatory => {
+                                // a nested arglist, as in `((a b) c)`
+                                let pattern = self.parse_arglist(spec)?;
+                                params.push(Param {
+                                    var: ParamVar::Pattern(obj, pattern),
+                                    arg_type,
+                                    default: None,
+                                    supplied_p: None,
+                                });
+                            }
+                            ArgType::Optional | ArgType::Key => {
+                                // (NAME &optional DEFAULT SUPPLIED-P)
+                                let spec = self.arg_forms(obj)?;
+                                if spec.len() > 3 {
+                                    let n = spec.len() as i32;
+                                    return Err(ErrorKind::WrongArgsCount(n, 1, Some(3)).into());
+                                }
+                                params.push(Param {
+                                    var: ParamVar::Symbol(symbol(spec[0])?),
+                                    arg_type,
+                                    default: spec.get(1).cloned(),
+                                    supplied_p: match spec.get(2) {
+                                        Some(&supplied_p) => Some(symbol(supplied_p)?),
+                                        None => None,
+                                    },
+                                });
+                            }
+                            ArgType::Rest => {
+                                symbol(obj)?;
                             }
                         }
-                        ArgType::Rest => {
-                            max_args = None;
-                        }
+                    } else {
+                        symbol(obj)?;
                     }
                 }
-            } else {
-                break;
             }
         }
+        Ok(params)
+    }
+
+    fn acceptable_range(&mut self, arglist: &ConsCell) -> Result<(i32, Option<i32>)> {
+        debug!("Checking acceptable range for arglist {}", arglist);
+        let params = self.parse_arglist(arglist)?;
+        let (min_args, max_args) = params_range(&params);
         debug!("Got acceptable range [{}, {:?}]", min_args, max_args);
         Ok((min_args, max_args))
     }
 
     fn get_args_for_lisp_func(&mut self, arglist: &ConsCell, n_args: i32) -> Object {
-        use types::function::ArgType;
         debug!("getting arglist {}", arglist);
-        // iterate through an arglist, pop()ing off the stack for each
-        // item and binding the arg to the pop()ed value.
-        let mut iter = list::iter(arglist);
-        let mut arg_type = ArgType::Mandatory;
-        let mut consumed = 0;
+        let params = try_rlisp_err!(self : self.parse_arglist(arglist));
         let mut args = Vec::with_capacity(n_args as _);
-        loop {
-            let res = iter.improper_next();
-            if let ConsIteratorResult::More(sym) = res {
-                let sym = into_type_or_error!(self : sym => *const Symbol);
-                let arg_name: &[u8] = unsafe { &*sym }.as_ref();
-                if arg_name == b"&optional" {
-                    debug!("switching to &optional args");
-                    arg_type = ArgType::Optional;
-                } else if arg_name == b"&rest" {
-                    debug!("switching to &rest args");
-                    arg_type = ArgType::Rest;
-                } else {
-                    match arg_type {
-                        ArgType::Mandatory => {
-                            let arg = pop_bubble!(self);
-                            args.push((sym, arg));
-                            consumed += 1;
-                        }
-                        ArgType::Optional => {
-                            if consumed < n_args {
-                                let arg = pop_bubble!(self);
-                                debug!("get_args_for_lisp_func(): popped the arg {}", arg);
-                                args.push((sym, arg));
-                                consumed += 1;
-                            } else {
-                                args.push((sym, Object::nil()));
-                            }
-                        }
-                        ArgType::Rest => {
-                            let mut head = Object::nil();
-                            while consumed < n_args {
-                                consumed += 1;
-                                let arg = pop_bubble!(self);
-                                debug!("get_args_for_lisp_func(): popped the arg {}", arg);
-                                let conscell = ConsCell::new(arg, head);
-                                head = self.alloc(conscell);
-                            }
-                            args.push((
-                                sym,
-                                if head != Object::nil() {
-                                    self.list_reverse(unsafe { head.into_unchecked() })
-                                } else {
-                                    head
-                                },
-                            ));
-                        }
+        for _ in 0..n_args {
+            let arg = pop_bubble!(self).deref_place();
+            debug!("get_args_for_lisp_func(): popped the arg {}", arg);
+            args.push(arg);
+        }
+        self.bind_params(&params, Object::from(arglist as *const ConsCell), &args)
+    }
+
+    fn bind_params(&mut self, params: &[Param], arglist: Object, args: &[Object]) -> Object {
+        // Bind `args` to `params`, which were read from `arglist`, in
+        // a new scope. Every parameter is first bound to `nil`. Then
+        // the args are assigned to their parameters, and finally the
+        // defaults of the parameters which weren't passed an arg are
+        // evaluated in order. By then everything is safe from the gc
+        // in the new scope, and each default can see the parameters
+        // before it. If binding fails, the scope is ended.
+        let mut scope = Vec::new();
+        param_symbols(params, &mut scope);
+        self.new_scope(&scope);
+
+        let mut defaults = Vec::new();
+        let res = self.assign_args(params, args, &mut defaults);
+        if res.errorp() {
+            self.end_scope();
+            return res;
+        }
+
+        // the default forms belong to `arglist`, which is kept on the
+        // stack while they are evaluated
+        self.push(arglist);
+        for (name, default) in defaults {
+            let val = self.evaluate(default).deref_place();
+            if val.errorp() {
+                self.pop();
+                self.end_scope();
+                return val;
+            }
+            self.set_symbol(name, val);
+        }
+        self.pop();
+        Object::nil()
+    }
+
+    fn assign_args(
+        &mut self,
+        params: &[Param],
+        args: &[Object],
+        defaults: &mut Vec<(*const Symbol, Object)>,
+    ) -> Object {
+        // Assign `args` to the symbols in `params`, which are already
+        // bound, and push the defaults to be evaluated for the
+        // parameters which weren't passed an arg onto `defaults`.
+        let mut supplied = vec![false; params.len()];
+        let mut consumed = 0;
+        let mut takes_keys = false;
+        for (i, param) in params.iter().enumerate() {
+            match param.arg_type {
+                ArgType::Mandatory | ArgType::Optional => {
+                    if consumed < args.len() {
+                        bubble!(self.assign_param(&param.var, args[consumed], defaults));
+                        supplied[i] = true;
+                        consumed += 1;
                     }
                 }
-            } else if let ConsIteratorResult::Final(Some(_)) = res {
-                let e: Error = ErrorKind::ImproperList.into();
-                let e: RlispError = e.into();
-                return self.alloc(e);
-            } else {
-                break;
+                ArgType::Rest => {
+                    // the rest list also holds any keyword args
+                    let rest = self.list_from_vec(args[consumed..].to_vec());
+                    bubble!(self.assign_param(&param.var, rest, defaults));
+                    supplied[i] = true;
+                }
+                ArgType::Key => {
+                    takes_keys = true;
+                }
+            }
+        }
+
+        if takes_keys {
+            let keys: Vec<Object> = params
+                .iter()
+                .map(|param| match (param.arg_type, &param.var) {
+                    (ArgType::Key, &ParamVar::Symbol(name)) => {
+                        let name: &[u8] = unsafe { &*name }.as_ref();
+                        let mut keyword = vec![b':'];
+                        keyword.extend_from_slice(name);
+                        Object::from(self.make_symbol(&keyword))
+                    }
+                    _ => Object::nil(),
+                })
+                .collect();
+            for pair in args[consumed..].chunks(2) {
+                let key = pair[0];
+                let i = if let Some(i) = keys.iter().position(|&k| !k.nilp() && k == key) {
+                    i
+                } else {
+                    let allowed = keys.iter().cloned().filter(|k| !k.nilp()).collect();
+                    let allowed = self.list_from_vec(allowed);
+                    return self.alloc(RlispError::unknown_keyword(key, allowed));
+                };
+                if pair.len() < 2 {
+                    return self.alloc(RlispError::missing_keyword_value(key));
+                }
+                // if a key is passed more than once, the first wins
+                if !supplied[i] {
+                    bubble!(self.assign_param(&params[i].var, pair[1], defaults));
+                    supplied[i] = true;
+                }
+            }
+        }
+
+        for (i, param) in params.iter().enumerate() {
+            if let Some(supplied_p) = param.supplied_p {
+                self.set_symbol(supplied_p, supplied[i].into());
+            }
+            if let (false, Some(default), &ParamVar::Symbol(name)) =
+                (supplied[i], param.default, &param.var)
+            {
+                defaults.push((name, default));
             }
         }
-        self.new_scope(&args);
         Object::nil()
     }
+
+    fn assign_param(
+        &mut self,
+        var: &ParamVar,
+        arg: Object,
+        defaults: &mut Vec<(*const Symbol, Object)>,
+    ) -> Object {
+        match *var {
+            ParamVar::Symbol(name) => {
+                self.set_symbol(name, arg);
+                Object::nil()
+            }
+            ParamVar::Pattern(pattern, ref params) => {
+                let args = match self.destructure(pattern, params, arg) {
+                    Ok(args) => args,
+                    Err(e) => return e,
+                };
+                self.assign_args(params, &args, defaults)
+            }
+        }
+    }
+
+    fn destructure(
+        &mut self,
+        pattern: Object,
+        params: &[Param],
+        value: Object,
+    ) -> ::std::result::Result<Vec<Object>, Object> {
+        // Return the elements of the list `value`, which is to be
+        // bound to `params`, read from the nested arglist `pattern`,
+        // or signal a `destructuring-error` if it is the wrong shape.
+        let value = value.deref_place();
+        let mut elements = Vec::new();
+        let mut rest = value;
+        while let Some(&ConsCell { car, cdr, .. }) = <&ConsCell>::maybe_from(rest) {
+            elements.push(car.deref_place());
+            rest = cdr;
+        }
+        let (min_args, max_args) = params_range(params);
+        let n = elements.len() as i32;
+        if !rest.nilp() || n < min_args || max_args.map_or(false, |max| n > max) {
+            Err(self.alloc(RlispError::destructuring_mismatch(pattern, value)))
+        } else {
+            Ok(elements)
+        }
+    }
     fn pop_args_from_lisp_func(&mut self) {
         // This method is called after evaluating a LispFn to unbind
         // the args
@@ -351,6 +965,48 @@ pub trait Evaluator
     }
 }
 
+// the minimum and maximum number of args which can be bound to
+// `params`, or `None` if there is no maximum
+fn params_range(params: &[Param]) -> (i32, Option<i32>) {
+    let mut min_args: i32 = 0;
+    let mut max_args: Option<i32> = Some(0);
+    for param in params {
+        match param.arg_type {
+            ArgType::Mandatory => {
+                min_args += 1;
+                if let Some(ref mut ma) = max_args {
+                    *ma += 1;
+                }
+            }
+            ArgType::Optional => {
+                if let Some(ref mut ma) = max_args {
+                    *ma += 1;
+                }
+            }
+            // the number of keyword args is checked when they are
+            // bound
+            ArgType::Rest | ArgType::Key => {
+                max_args = None;
+            }
+        }
+    }
+    (min_args, max_args)
+}
+
+// collects every symbol bound by `params`, including those in nested
+// arglists, into `scope`, bound to `nil`
+fn param_symbols(params: &[Param], scope: &mut Vec<(*const Symbol, Object)>) {
+    for param in params {
+        match param.var {
+            ParamVar::Symbol(name) => scope.push((name, Object::nil())),
+            ParamVar::Pattern(_, ref params) => param_symbols(params, scope),
+        }
+        if let Some(supplied_p) = param.supplied_p {
+            scope.push((supplied_p, Object::nil()));
+        }
+    }
+}
+
 impl Evaluator for lisp::Lisp {
     fn call_rust_func(&mut self, func: &mut RlispBuiltinFunc, n_args: i32) -> Object {
         debug!("calling a builtin function");
@@ -360,4 +1016,19 @@ impl Evaluator for lisp::Lisp {
         debug!("calling a special form");
         func(self, n_args)
     }
+    fn pending_tail_form(&mut self) -> &mut Option<Object> {
+        &mut self.tail_form
+    }
+    fn call_stack(&mut self) -> &mut Vec<Object> {
+        &mut self.call_stack
+    }
+    fn handlers(&mut self) -> &mut Vec<Handler> {
+        &mut self.handlers
+    }
+    fn eval_depth(&mut self) -> &mut usize {
+        &mut self.eval_depth
+    }
+    fn max_eval_depth(&self) -> usize {
+        self.max_eval_depth
+    }
 }
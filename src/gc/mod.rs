@@ -15,7 +15,7 @@ use std::mem;
 pub type GcMark = usize;
 
 pub trait GarbageCollector
-    : lisp::stack_storage::Stack + lisp::allocate::AllocObject
+    : lisp::stack_storage::Stack + lisp::allocate::AllocObject + lisp::SourceTracking
 // This trait is implemented by lisp::Lisp
 // its methods amount to a simple mark+sweep garbage collector
 {
@@ -38,6 +38,7 @@ pub trait GarbageCollector
         for obj in old_objs.drain(..) {
             if obj.should_dealloc(self.current_marking()) {
                 debug!("{} is not marked, deallocating it", obj);
+                self.forget_span(obj);
                 unsafe { self.dealloc(obj) }
             } else {
                 self.objects_mut().push(obj);
@@ -31,6 +31,10 @@ mod result {
                 description("something went wrong while parsing a float"),
                 display("something went wrong while parsing a float"),
             }
+            AtSource(location: String) {
+                description("an error at a known location in the source"),
+                display("at {}", location),
+            }
             UnclosedList {
                 description("met EOF before a list was closed"),
                 display("met EOF before a list was closed"),
@@ -39,6 +43,14 @@ mod result {
                 description("met EOF before a string was closed"),
                 display("met EOF before a string was closed"),
             }
+            UnknownDispatch(c: char) {
+                description("met an unknown dispatch character after `#`"),
+                display("unknown read syntax `#{}`", c),
+            }
+            UnknownCharName(name: String) {
+                description("met an unknown character name after `#\\`"),
+                display("unknown character name `{}`", name),
+            }
             UnexpectedEOF {
                 description("met EOF before finished parsing"),
                 display("met EOF before finished parsing"),
@@ -92,6 +104,7 @@ mod gc;
 mod reader;
 mod evaluator;
 mod math;
+mod strings;
 mod symbols_table;
 
 pub mod repl;
@@ -25,6 +25,21 @@ pub trait AllocObject {
         self.objects_mut().push(obj);
         obj
     }
+    fn alloc_num(&mut self, num: RlispNum) -> Object {
+        // integers which don't fit in an immediate are boxed up in an
+        // `RlispBignum`, and ratios in an `RlispRatio`
+        match num {
+            RlispNum::Int(i) => Object::from(i),
+            RlispNum::Float(f) => Object::from(f),
+            RlispNum::Big(big) => match big.to_i32() {
+                Some(i) => Object::from(i),
+                None => self.alloc(RlispBignum::from(big)),
+            },
+            RlispNum::Ratio(numerator, denominator) => {
+                self.alloc(RlispRatio::new(numerator, denominator))
+            }
+        }
+    }
     unsafe fn dealloc(&mut self, to_dealloc: Object) {
         use types::conversions::FromUnchecked;
         // deallocate an object
@@ -32,8 +47,13 @@ pub trait AllocObject {
             RlispType::Number
             | RlispType::Float
             | RlispType::Integer
-            | RlispType::Bool
-            | RlispType::Place => warn!("attempt to dealloc a by-value object"),
+            | RlispType::Rational
+            | RlispType::Char
+            | RlispType::Bool => warn!("attempt to dealloc a by-value object"),
+            RlispType::Place => match Place::from_unchecked(to_dealloc) {
+                Place::Slot(_) => warn!("attempt to dealloc a by-value object"),
+                Place::Element(element) => self.low_level_dealloc(element),
+            },
             RlispType::Cons => {
                 self.low_level_dealloc(<*const ConsCell>::from_unchecked(to_dealloc))
             }
@@ -50,6 +70,33 @@ pub trait AllocObject {
             RlispType::Namespace => {
                 self.low_level_dealloc(<*const Namespace>::from_unchecked(to_dealloc))
             }
+            RlispType::Vector => {
+                // unlike the other types, a vector owns a heap buffer
+                // which has to be freed along with it
+                let vector = <*mut RlispVector>::from_unchecked(to_dealloc);
+                ptr::drop_in_place(vector);
+                self.low_level_dealloc(vector)
+            }
+            RlispType::HashTable => {
+                let table = <*mut RlispHashTable>::from_unchecked(to_dealloc);
+                ptr::drop_in_place(table);
+                self.low_level_dealloc(table)
+            }
+            RlispType::StringBuilder => {
+                let builder = <*mut RlispStringBuilder>::from_unchecked(to_dealloc);
+                ptr::drop_in_place(builder);
+                self.low_level_dealloc(builder)
+            }
+            RlispType::Bignum => {
+                let bignum = <*mut RlispBignum>::from_unchecked(to_dealloc);
+                ptr::drop_in_place(bignum);
+                self.low_level_dealloc(bignum)
+            }
+            RlispType::Ratio => {
+                let ratio = <*mut RlispRatio>::from_unchecked(to_dealloc);
+                ptr::drop_in_place(ratio);
+                self.low_level_dealloc(ratio)
+            }
         }
     }
     unsafe fn low_level_dealloc<T>(&mut self, to_dealloc: *const T) {
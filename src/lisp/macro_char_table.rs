@@ -4,6 +4,7 @@ use symbols_table::SymbolLookup;
 
 pub trait MacroChars: SymbolLookup {
     fn check_macro_char(&mut self, byte: u8) -> Option<Object>;
+    fn check_macro_char_pair(&mut self, first: u8, second: u8) -> Option<Object>;
 }
 
 impl MacroChars for lisp::Lisp {
@@ -17,4 +18,14 @@ impl MacroChars for lisp::Lisp {
         };
         Some(Object::from(self.make_symbol(symbol)))
     }
+    fn check_macro_char_pair(&mut self, first: u8, second: u8) -> Option<Object> {
+        let symbol = {
+            if let Some(sym) = self.macro_char_pairs.get(&(first, second)) {
+                *sym
+            } else {
+                return None;
+            }
+        };
+        Some(Object::from(self.make_symbol(symbol)))
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use types::*;
 use types::into_object::*;
@@ -6,10 +6,14 @@ use types::conversions::*;
 use builtins;
 use std::convert;
 use symbols_table::SymbolLookup;
+use std::rc::Rc;
 
 mod macro_char_table;
 pub use self::macro_char_table::MacroChars;
 
+mod source_table;
+pub use self::source_table::{SourcePos, SourceSpan, SourceTracking};
+
 pub mod stack_storage {
     use types::*;
     use result::*;
@@ -20,6 +24,7 @@ pub mod stack_storage {
         fn pop(&mut self) -> Object;
         fn stack_vec(&self) -> &Vec<Object>;
         fn clean_stack(&mut self);
+        fn discard(&mut self, n: usize);
     }
     impl Stack for lisp::Lisp {
         fn push(&mut self, obj: Object) {
@@ -40,24 +45,115 @@ pub mod stack_storage {
         fn clean_stack(&mut self) {
             self.stack = Vec::new();
         }
+        fn discard(&mut self, n: usize) {
+            // pop and ignore the top `n` objects
+            let len = self.stack.len();
+            debug_assert!(n <= len);
+            self.stack.truncate(len - n);
+        }
     }
 }
 
 pub mod allocate;
 
-const INITIAL_MACRO_CHARS: &[(u8, &[u8])] = &[(b'\'', b"quote")];
+const INITIAL_MACRO_CHARS: &[(u8, &[u8])] = &[
+    (b'\'', b"quote"),
+    (b'`', b"backquote"),
+    (b',', b"comma"),
+];
+
+// the default value of `Lisp::max_eval_depth`
+const DEFAULT_MAX_EVAL_DEPTH: usize = 1000;
+
+/// The stack size of the thread started by `on_eval_stack`. In a
+/// debug build, a level of evaluation which calls back into lisp from
+/// a builtin, like `mapcar`, takes about 8KB of Rust stack; a thread
+/// with Rust's default 2MB stack overflows after 250 to 300 such
+/// levels. This leaves room for several times the default
+/// `max_eval_depth`.
+pub const EVAL_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Runs `f`, which should create and use a `Lisp`, on a new thread
+/// with an `EVAL_STACK_SIZE` stack, so that evaluation can nest
+/// `DEFAULT_MAX_EVAL_DEPTH` deep without overflowing it. Panics in `f`
+/// are passed on to the caller.
+pub fn on_eval_stack<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let thread = ::std::thread::Builder::new()
+        .stack_size(EVAL_STACK_SIZE)
+        .spawn(f)
+        .unwrap();
+    match thread.join() {
+        Ok(res) => res,
+        Err(panic) => ::std::panic::resume_unwind(panic),
+    }
+}
+
+// two-byte macro chars, which take precedence over the single-byte
+// macro char that is their first byte
+const INITIAL_MACRO_CHAR_PAIRS: &[((u8, u8), &[u8])] = &[((b',', b'@'), b"comma-at")];
+
+// an error handler established by `handler-bind`, or by
+// `handler-case` or `catch-error` if `function` is `None`
+#[derive(Copy, Clone)]
+pub struct Handler {
+    pub name: Object,
+    pub function: Option<Object>,
+}
 
 pub struct Lisp {
     pub symbols: Scope,
     pub syms_in_memory: HashMap<Vec<u8>, *const Symbol>,
     macro_chars: HashMap<u8, &'static [u8]>,
+    macro_char_pairs: HashMap<(u8, u8), &'static [u8]>,
     pub stack: Vec<Object>,
     pub current_gc_mark: ::gc::GcMark,
     pub alloced_objects: Vec<Object>,
     pub gc_threshold: usize,
+    pub tail_form: Option<Object>,
+    pub eval_depth: usize,
+    // evaluating deeper than this signals a `stack-depth-exceeded-error`
+    // instead of overflowing the Rust stack
+    pub max_eval_depth: usize,
+    // the names of the functions currently being called, outermost
+    // first, or `nil` for anonymous functions
+    pub call_stack: Vec<Object>,
+    source_file: Option<Rc<str>>,
+    source_pos: SourcePos,
+    source_spans: HashMap<*const ConsCell, SourceSpan>,
+    // the active error handlers, innermost last
+    pub handlers: Vec<Handler>,
+    // the names of the active restarts, innermost last, along with
+    // the id of the `restart-case` which established each
+    pub restarts: Vec<(Object, usize)>,
+    pub next_restart_id: usize,
+    // the ids of the blocks currently being evaluated
+    pub blocks: Vec<usize>,
+    pub next_block_id: usize,
+    // the tags of the active `catch`es, innermost last
+    pub catch_tags: Vec<Object>,
+    // the symbols declared special by `defvar` or `defparameter`,
+    // which `let` binds dynamically
+    pub special_vars: HashSet<*const Symbol>,
 }
 
 impl Lisp {
+    /// Start reading the file named `name`. Lists read after this
+    /// remember where in `name` they came from, and errors signaled
+    /// while evaluating them report it.
+    pub fn begin_source(&mut self, name: &str) {
+        self.source_file = Some(Rc::from(name));
+        self.source_pos = SourcePos::default();
+    }
+    /// Stop recording source locations, as for input typed into the
+    /// REPL.
+    pub fn end_source(&mut self) {
+        self.source_file = None;
+        self.source_pos = SourcePos::default();
+    }
     fn source_builtins(&mut self, mut builtin_funcs: builtins::RlispBuiltins) {
         info!("sourcing builtin functions");
         use lisp::allocate::AllocObject;
@@ -118,10 +214,25 @@ impl Default for Lisp {
             symbols: vec![],
             syms_in_memory: HashMap::new(),
             macro_chars: INITIAL_MACRO_CHARS.iter().cloned().collect(),
+            macro_char_pairs: INITIAL_MACRO_CHAR_PAIRS.iter().cloned().collect(),
             current_gc_mark: 1,
             stack: Vec::new(),
             alloced_objects: Vec::new(),
             gc_threshold: 16,
+            tail_form: None,
+            eval_depth: 0,
+            max_eval_depth: DEFAULT_MAX_EVAL_DEPTH,
+            call_stack: Vec::new(),
+            source_file: None,
+            source_pos: SourcePos::default(),
+            source_spans: HashMap::new(),
+            handlers: Vec::new(),
+            restarts: Vec::new(),
+            next_restart_id: 0,
+            blocks: Vec::new(),
+            next_block_id: 0,
+            catch_tags: Vec::new(),
+            special_vars: HashSet::new(),
         };
         let global_namespace_name = me.alloc_sym(b"global-namespace");
         let global_namespace = me.alloc(Namespace::default().with_name(global_namespace_name));
@@ -131,6 +242,8 @@ impl Default for Lisp {
         me.source_special_forms(builtins::make_special_forms());
         me.source_builtins(builtins::make_builtins());
         me.source_builtins(::math::math_builtins::make_builtins());
+        me.source_builtins(::list::list_builtins::make_builtins());
+        me.source_builtins(::strings::string_builtins::make_builtins());
         me
     }
 }
@@ -0,0 +1,96 @@
+use lisp;
+use types::*;
+use types::conversions::*;
+use std::fmt;
+use std::rc::Rc;
+
+// A position in the input being read. `line` and `col` count from 1,
+// and `offset` is the number of bytes before the position.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SourcePos {
+    pub offset: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl SourcePos {
+    pub fn advance(&mut self, byte: u8) {
+        self.offset += 1;
+        if byte == b'\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+    }
+}
+
+impl Default for SourcePos {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+}
+
+// The text a form was read from: the bytes from `start` up to but not
+// including `end` in the file `file`.
+#[derive(Clone, Debug)]
+pub struct SourceSpan {
+    pub file: Rc<str>,
+    pub start: SourcePos,
+    pub end: SourcePos,
+}
+
+impl fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.start.line, self.start.col)
+    }
+}
+
+// The reader keeps its position in the input up to date through
+// `source_pos`. While reading a named file (see
+// `Lisp::begin_source`), it also records the span of each list it
+// reads in a side table keyed by the address of the list's first
+// `ConsCell`, which the evaluator uses to say where errors happened.
+pub trait SourceTracking {
+    fn source_pos(&mut self) -> &mut SourcePos;
+    fn source_file(&self) -> Option<Rc<str>>;
+    fn record_span(&mut self, form: Object, start: SourcePos);
+    fn source_span(&self, form: Object) -> Option<SourceSpan>;
+    fn forget_span(&mut self, form: Object);
+}
+
+impl SourceTracking for lisp::Lisp {
+    fn source_pos(&mut self) -> &mut SourcePos {
+        &mut self.source_pos
+    }
+    fn source_file(&self) -> Option<Rc<str>> {
+        self.source_file.clone()
+    }
+    fn record_span(&mut self, form: Object, start: SourcePos) {
+        if let Some(file) = self.source_file.clone() {
+            if let Some(cons) = <&ConsCell>::maybe_from(form) {
+                let span = SourceSpan {
+                    file,
+                    start,
+                    end: self.source_pos,
+                };
+                self.source_spans.insert(cons as *const ConsCell, span);
+            }
+        }
+    }
+    fn source_span(&self, form: Object) -> Option<SourceSpan> {
+        let cons = <&ConsCell>::maybe_from(form)?;
+        self.source_spans.get(&(cons as *const ConsCell)).cloned()
+    }
+    fn forget_span(&mut self, form: Object) {
+        if !self.source_spans.is_empty() {
+            if let Some(cons) = <&ConsCell>::maybe_from(form) {
+                self.source_spans.remove(&(cons as *const ConsCell));
+            }
+        }
+    }
+}
@@ -0,0 +1,330 @@
+/*
+The list library: functions which build, take apart and search lists,
+including the higher-order ones which call back into the evaluator.
+
+A builtin's args are popped off the stack before its body runs, so
+nothing would keep them from being gc'd by a pass triggered during a
+callback. The functions which take callbacks therefore run inside
+`rooted`, which keeps their args on the stack, and build their results
+in stack slots. An arg may be a `Place` in a namespace which a tail
+call has already left, so they deref their args before rooting them,
+and only use the derefed values.
+ */
+use builtins::*;
+use types::*;
+use types::conversions::*;
+use lisp;
+use lisp::allocate::AllocObject;
+use lisp::stack_storage::Stack;
+use evaluator::Evaluator;
+use symbols_table::SymbolLookup;
+use list::ListOps;
+use std::result;
+
+// calls `f` with `objects` kept on the stack, and cleans up whatever
+// `f` left on the stack, even if it returns early with an error
+fn rooted<F>(l: &mut lisp::Lisp, objects: &[Object], f: F) -> Object
+where
+    F: FnOnce(&mut lisp::Lisp) -> Object,
+{
+    let depth = l.stack.len();
+    for obj in objects {
+        l.push(*obj);
+    }
+    let res = f(l);
+    let extra = l.stack.len() - depth;
+    l.discard(extra);
+    res
+}
+
+// the elements of the proper list `list`
+fn elements(l: &mut lisp::Lisp, list: Object) -> result::Result<Vec<Object>, Object> {
+    let list = list.deref_place();
+    if !(list.nilp() || <&ConsCell>::is_type(list)) {
+        let e = RlispError::wrong_type(l.type_name(RlispType::Cons),
+                                       l.type_name(list.what_type()));
+        return Err(l.alloc(e));
+    }
+    match l.arg_forms(list) {
+        Ok(elements) => Ok(elements.into_iter().map(Object::deref_place).collect()),
+        Err(e) => Err(l.alloc(RlispError::from(e))),
+    }
+}
+
+// calls `function` with `args`, returning the value rather than a
+// place holding it
+fn call(l: &mut lisp::Lisp, function: Object, args: &[Object]) -> Object {
+    l.call_function(function, args).deref_place()
+}
+
+// whether `item` and `element` match, using `test` if it is given
+// and `eq` otherwise
+fn matches(l: &mut lisp::Lisp, test: Option<Object>, item: Object, element: Object) -> Object {
+    if let Some(test) = test {
+        call(l, test, &[item, element])
+    } else {
+        Object::from(item == element)
+    }
+}
+
+// a stable merge sort of `elements`, which are pairs of an element
+// and the key it is sorted by, using the Lisp predicate `predicate`
+fn merge_sort(
+    l: &mut lisp::Lisp,
+    predicate: Object,
+    elements: Vec<(Object, Object)>,
+) -> result::Result<Vec<(Object, Object)>, Object> {
+    if elements.len() <= 1 {
+        return Ok(elements);
+    }
+    let mut right = elements;
+    let left = right.drain(..right.len() / 2).collect();
+    let left = merge_sort(l, predicate, left)?;
+    let right = merge_sort(l, predicate, right)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        // take from the right only if it belongs strictly before the
+        // left, so that equal elements keep their order
+        let before = call(l, predicate, &[right[j].1, left[i].1]);
+        if before.errorp() {
+            return Err(before);
+        }
+        if bool::from(before) {
+            merged.push(right[j]);
+            j += 1;
+        } else {
+            merged.push(left[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&left[i..]);
+    merged.extend_from_slice(&right[j..]);
+    Ok(merged)
+}
+
+pub fn make_builtins() -> RlispBuiltins {
+    builtin_functions!{
+        l = lisp;
+        "length" (list) -> {
+            let elements = try_or_return!(elements(l, list));
+            Object::from(elements.len() as i32)
+        },
+        "reverse" (list) -> {
+            let mut elements = try_or_return!(elements(l, list));
+            elements.reverse();
+            l.list_from_vec(elements)
+        },
+        "append" (&rest lists) -> {
+            // every list but the last is copied, and the last becomes
+            // the tail of the result
+            let mut lists = try_or_return!(elements(l, lists));
+            if let Some(last) = lists.pop() {
+                let mut all = Vec::new();
+                for list in lists {
+                    all.extend(try_or_return!(elements(l, list)));
+                }
+                all.push(last);
+                l.list_improper_from_vec(all)
+            } else {
+                Object::nil()
+            }
+        },
+        "last" (list &optional n) -> {
+            // the last `n` conses of `list`, or the last one if `n`
+            // isn't given
+            let n = if n.nilp() { 1 } else { into_type_or_error!(l : n => i32) };
+            let len = try_or_return!(elements(l, list)).len() as i32;
+            let mut tail = list.deref_place();
+            for _ in 0..(len - n.max(0)) {
+                if let Some(&ConsCell { cdr, .. }) = <&ConsCell>::maybe_from(tail) {
+                    tail = cdr;
+                }
+            }
+            tail
+        },
+        "butlast" (list &optional n) -> {
+            // a copy of `list` without its last `n` elements, or its
+            // last element if `n` isn't given
+            let n = if n.nilp() { 1 } else { into_type_or_error!(l : n => i32) };
+            let mut elements = try_or_return!(elements(l, list));
+            let len = elements.len() as i32;
+            elements.truncate((len - n.max(0)).max(0) as usize);
+            l.list_from_vec(elements)
+        },
+        "mapcar" (function list &rest lists) -> {
+            // calls `function` with the first elements of each list,
+            // then the second, and so on until the shortest list runs
+            // out, and returns a list of the results
+            let (function, list, lists) =
+                (function.deref_place(), list.deref_place(), lists.deref_place());
+            rooted(l, &[function, list, lists], |l| {
+                let mut all = vec![try_or_return!(elements(l, list))];
+                for list in try_or_return!(elements(l, lists)) {
+                    all.push(try_or_return!(elements(l, list)));
+                }
+                let len = all.iter().map(Vec::len).min().unwrap_or(0);
+                let results = l.stack.len();
+                for i in 0..len {
+                    let args: Vec<_> = all.iter().map(|list| list[i]).collect();
+                    let res = bubble!(call(l, function, &args));
+                    l.push(res);
+                }
+                let results = l.stack.split_off(results);
+                l.list_from_vec(results)
+            })
+        },
+        "mapc" (function list &rest lists) -> {
+            // like `mapcar`, but only for effect. Returns `list`.
+            let (function, list, lists) =
+                (function.deref_place(), list.deref_place(), lists.deref_place());
+            rooted(l, &[function, list, lists], |l| {
+                let mut all = vec![try_or_return!(elements(l, list))];
+                for list in try_or_return!(elements(l, lists)) {
+                    all.push(try_or_return!(elements(l, list)));
+                }
+                let len = all.iter().map(Vec::len).min().unwrap_or(0);
+                for i in 0..len {
+                    let args: Vec<_> = all.iter().map(|list| list[i]).collect();
+                    bubble!(call(l, function, &args));
+                }
+                list
+            })
+        },
+        "reduce" (function list &rest options) -> {
+            // combines the elements of `list` with `function`, from the
+            // left, or from the right if `:from-end` is true, starting
+            // with `:initial-value` if it is given
+            let (function, list, options) =
+                (function.deref_place(), list.deref_place(), options.deref_place());
+            rooted(l, &[function, list, options], |l| {
+                let options = try_or_return!(keyword_options(l, options,
+                                                             &[b":initial-value", b":from-end"]));
+                let mut elements = try_or_return!(elements(l, list));
+                let from_end = options[1].map_or(false, bool::from);
+                if from_end {
+                    elements.reverse();
+                }
+                let mut elements = elements.into_iter();
+                let mut acc = if let Some(initial) = options[0] {
+                    initial
+                } else if let Some(first) = elements.next() {
+                    first
+                } else {
+                    // with no elements and no initial value,
+                    // `function` is called with no args
+                    return call(l, function, &[]);
+                };
+                let acc_slot = l.stack.len();
+                l.push(acc);
+                for el in elements {
+                    let args = if from_end { [el, acc] } else { [acc, el] };
+                    acc = bubble!(call(l, function, &args));
+                    l.stack[acc_slot] = acc;
+                }
+                acc
+            })
+        },
+        "remove-if" (predicate list) -> {
+            // a list of the elements of `list` which don't satisfy
+            // `predicate`
+            let (predicate, list) = (predicate.deref_place(), list.deref_place());
+            rooted(l, &[predicate, list], |l| {
+                let mut kept = Vec::new();
+                for el in try_or_return!(elements(l, list)) {
+                    if !bool::from(bubble!(call(l, predicate, &[el]))) {
+                        kept.push(el);
+                    }
+                }
+                l.list_from_vec(kept)
+            })
+        },
+        "find" (item list &rest options) -> {
+            // the first element of `list` which matches `item`,
+            // compared with `:test` (by default `eq`) after applying
+            // `:key` to it
+            let (item, list, options) =
+                (item.deref_place(), list.deref_place(), options.deref_place());
+            rooted(l, &[item, list, options], |l| {
+                let options = try_or_return!(keyword_options(l, options, &[b":test", b":key"]));
+                for el in try_or_return!(elements(l, list)) {
+                    let key = if let Some(key) = options[1] {
+                        let key = bubble!(call(l, key, &[el]));
+                        l.push(key);
+                        key
+                    } else {
+                        el
+                    };
+                    if bool::from(bubble!(matches(l, options[0], item, key))) {
+                        return el;
+                    }
+                }
+                Object::nil()
+            })
+        },
+        "member" (item list &rest options) -> {
+            // the tail of `list` starting with the first element which
+            // matches `item`, compared with `:test` (by default `eq`)
+            let (item, list, options) =
+                (item.deref_place(), list.deref_place(), options.deref_place());
+            rooted(l, &[item, list, options], |l| {
+                let options = try_or_return!(keyword_options(l, options, &[b":test"]));
+                try_or_return!(elements(l, list));
+                let mut tail = list;
+                while let Some(&ConsCell { car, cdr, .. }) = <&ConsCell>::maybe_from(tail) {
+                    if bool::from(bubble!(matches(l, options[0], item, car.deref_place()))) {
+                        return tail;
+                    }
+                    tail = cdr;
+                }
+                Object::nil()
+            })
+        },
+        "assoc" (item alist &rest options) -> {
+            // the first pair in the list of conses `alist` whose car
+            // matches `item`, compared with `:test` (by default `eq`)
+            let (item, alist, options) =
+                (item.deref_place(), alist.deref_place(), options.deref_place());
+            rooted(l, &[item, alist, options], |l| {
+                let options = try_or_return!(keyword_options(l, options, &[b":test"]));
+                for pair in try_or_return!(elements(l, alist)) {
+                    if pair.nilp() {
+                        continue;
+                    }
+                    let &ConsCell { car, .. } = into_type_or_error!(l : pair => &ConsCell);
+                    if bool::from(bubble!(matches(l, options[0], item, car.deref_place()))) {
+                        return pair;
+                    }
+                }
+                Object::nil()
+            })
+        },
+        "sort" (list predicate &rest options) -> {
+            // a new list of the elements of `list`, stably sorted so
+            // that `predicate` is true of each element and any element
+            // after it which isn't equal to it. If `:key` is given,
+            // `predicate` is called on the results of applying it to
+            // the elements.
+            let (list, predicate, options) =
+                (list.deref_place(), predicate.deref_place(), options.deref_place());
+            rooted(l, &[list, predicate, options], |l| {
+                let options = try_or_return!(keyword_options(l, options, &[b":key"]));
+                let elements = try_or_return!(elements(l, list));
+                let mut keyed = Vec::with_capacity(elements.len());
+                for el in elements {
+                    let key = if let Some(key) = options[0] {
+                        let key = bubble!(call(l, key, &[el]));
+                        l.push(key);
+                        key
+                    } else {
+                        el
+                    };
+                    keyed.push((el, key));
+                }
+                let sorted = try_or_return!(merge_sort(l, predicate, keyed));
+                let sorted = sorted.into_iter().map(|(el, _)| el).collect();
+                l.list_from_vec(sorted)
+            })
+        },
+    }
+}
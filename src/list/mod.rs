@@ -10,6 +10,8 @@ use types::conversions::*;
 use lisp;
 use std::iter::{IntoIterator, Iterator};
 
+pub mod list_builtins;
+
 pub trait ListOps: lisp::allocate::AllocObject {
     fn list_improper_from_vec(&mut self, mut elems: Vec<Object>) -> Object {
         if elems.is_empty() {
@@ -0,0 +1,379 @@
+/*
+Arbitrary-precision integers, for the results of integer arithmetic
+which don't fit in an `i32`. A `BigInt` is a sign and a magnitude,
+which is stored as base 2^32 digits, least significant first, without
+any leading zeros. Zero has no digits and is never negative.
+
+None of the operations here are clever: multiplication is the
+schoolbook method, and division works a bit at a time. Rlisp's
+integers only become `BigInt`s when they outgrow an `i32`, so these
+are rarely used on very large numbers.
+*/
+
+use std::{cmp, fmt, ops};
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn cmp_magnitudes(a: &[u32], b: &[u32]) -> cmp::Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut sum = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &digit) in long.iter().enumerate() {
+        let total = u64::from(digit) + u64::from(*short.get(i).unwrap_or(&0)) + carry;
+        sum.push(total as u32);
+        carry = total >> 32;
+    }
+    if carry != 0 {
+        sum.push(carry as u32);
+    }
+    sum
+}
+
+// `a - b`, where `a` is at least `b`
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &digit) in a.iter().enumerate() {
+        let mut total = i64::from(digit) - i64::from(*b.get(i).unwrap_or(&0)) - borrow;
+        borrow = if total < 0 {
+            total += 1 << 32;
+            1
+        } else {
+            0
+        };
+        difference.push(total as u32);
+    }
+    trim(&mut difference);
+    difference
+}
+
+fn mul_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let total = u64::from(x) * u64::from(y) + u64::from(product[i + j]) + carry;
+            product[i + j] = total as u32;
+            carry = total >> 32;
+        }
+        product[i + b.len()] = carry as u32;
+    }
+    trim(&mut product);
+    product
+}
+
+// `(a / b, a % b)` for a nonzero `b`
+fn divrem_magnitudes(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_magnitudes(a, b) == cmp::Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for bit in (0..a.len() * 32).rev() {
+        // remainder = remainder * 2 + the next bit of `a`
+        let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+        for digit in &mut remainder {
+            let shifted = (*digit << 1) | carry;
+            carry = *digit >> 31;
+            *digit = shifted;
+        }
+        if carry != 0 {
+            remainder.push(carry);
+        }
+        if cmp_magnitudes(&remainder, b) != cmp::Ordering::Less {
+            remainder = sub_magnitudes(&remainder, b);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    trim(&mut quotient);
+    (quotient, remainder)
+}
+
+// `(a / divisor, a % divisor)` for a nonzero `divisor`
+fn divrem_small(a: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = 0u64;
+    for (i, &digit) in a.iter().enumerate().rev() {
+        let total = (remainder << 32) | u64::from(digit);
+        quotient[i] = (total / u64::from(divisor)) as u32;
+        remainder = total % u64::from(divisor);
+    }
+    trim(&mut quotient);
+    (quotient, remainder as u32)
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        trim(&mut magnitude);
+        let negative = negative && !magnitude.is_empty();
+        BigInt {
+            negative,
+            magnitude,
+        }
+    }
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+    pub fn abs(&self) -> Self {
+        Self::from_parts(false, self.magnitude.clone())
+    }
+    /// `self` as an `i32`, if it fits in one
+    pub fn to_i32(&self) -> Option<i32> {
+        match self.magnitude.len() {
+            0 => Some(0),
+            1 => {
+                let magnitude = i64::from(self.magnitude[0]);
+                let value = if self.negative { -magnitude } else { magnitude };
+                if value >= i64::from(::std::i32::MIN) && value <= i64::from(::std::i32::MAX) {
+                    Some(value as i32)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+    /// the nearest `f64` to `self`, or an infinity if it is too
+    /// large for one
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |acc, &digit| acc * 4_294_967_296.0 + f64::from(digit));
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+    /// `f` truncated to an integer, or `None` if it is infinite or NaN
+    pub fn from_f64(f: f64) -> Option<Self> {
+        if !f.is_finite() {
+            return None;
+        }
+        let f = f.trunc();
+        if f.abs() < 9_223_372_036_854_775_808.0 {
+            return Some(BigInt::from(f as i64));
+        }
+        // `f` is too big to have a fractional part, so it is its
+        // mantissa shifted left by its exponent
+        let bits = f.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as usize - 1075;
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let mut magnitude = vec![0u32; exponent / 32];
+        let shift = exponent % 32;
+        let low = (mantissa & 0xffff_ffff) << shift;
+        let high = ((mantissa >> 32) << shift) | (low >> 32);
+        magnitude.push(low as u32);
+        magnitude.push(high as u32);
+        magnitude.push((high >> 32) as u32);
+        Some(Self::from_parts(f < 0.0, magnitude))
+    }
+    /// parses a string of decimal digits, without a sign
+    pub fn parse(digits: &[u8]) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+        let mut magnitude: Vec<u32> = Vec::new();
+        for &digit in digits {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            magnitude = mul_magnitudes(&magnitude, &[10]);
+            magnitude = add_magnitudes(&magnitude, &[u32::from(digit - b'0')]);
+            trim(&mut magnitude);
+        }
+        Some(Self::from_parts(false, magnitude))
+    }
+    /// the greatest common divisor of `self` and `rhs`, which is never
+    /// negative
+    pub fn gcd(&self, rhs: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.abs(), rhs.abs());
+        while !b.is_zero() {
+            let (_, remainder) = divrem_magnitudes(&a.magnitude, &b.magnitude);
+            a = b;
+            b = Self::from_parts(false, remainder);
+        }
+        a
+    }
+    /// `self` multiplied by 2 to the power of `bits`
+    pub fn shl(&self, bits: usize) -> BigInt {
+        let mut magnitude = vec![0u32; bits / 32];
+        let shift = bits % 32;
+        let mut carry = 0u32;
+        for &digit in &self.magnitude {
+            let shifted = u64::from(digit) << shift;
+            magnitude.push(shifted as u32 | carry);
+            carry = (shifted >> 32) as u32;
+        }
+        magnitude.push(carry);
+        Self::from_parts(self.negative, magnitude)
+    }
+    /// `(self / rhs, self % rhs)`, truncating towards zero like
+    /// Rust's integer division, or `None` if `rhs` is zero
+    pub fn divrem(&self, rhs: &BigInt) -> Option<(BigInt, BigInt)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = divrem_magnitudes(&self.magnitude, &rhs.magnitude);
+        Some((
+            Self::from_parts(self.negative != rhs.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(i: i64) -> Self {
+        let magnitude = if i < 0 {
+            (i as u64).wrapping_neg()
+        } else {
+            i as u64
+        };
+        Self::from_parts(i < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl From<i32> for BigInt {
+    fn from(i: i32) -> Self {
+        BigInt::from(i64::from(i))
+    }
+}
+
+impl<'a> ops::Neg for &'a BigInt {
+    type Output = BigInt;
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl<'a> ops::Add for &'a BigInt {
+    type Output = BigInt;
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.negative == rhs.negative {
+            BigInt::from_parts(self.negative, add_magnitudes(&self.magnitude, &rhs.magnitude))
+        } else {
+            // the result has the sign of whichever has the larger
+            // magnitude
+            match cmp_magnitudes(&self.magnitude, &rhs.magnitude) {
+                cmp::Ordering::Less => BigInt::from_parts(
+                    rhs.negative,
+                    sub_magnitudes(&rhs.magnitude, &self.magnitude),
+                ),
+                _ => BigInt::from_parts(
+                    self.negative,
+                    sub_magnitudes(&self.magnitude, &rhs.magnitude),
+                ),
+            }
+        }
+    }
+}
+
+impl<'a> ops::Sub for &'a BigInt {
+    type Output = BigInt;
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        self + &(-rhs)
+    }
+}
+
+impl<'a> ops::Mul for &'a BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != rhs.negative,
+            mul_magnitudes(&self.magnitude, &rhs.magnitude),
+        )
+    }
+}
+
+impl cmp::Ord for BigInt {
+    fn cmp(&self, rhs: &BigInt) -> cmp::Ordering {
+        match (self.negative, rhs.negative) {
+            (false, true) => cmp::Ordering::Greater,
+            (true, false) => cmp::Ordering::Less,
+            (false, false) => cmp_magnitudes(&self.magnitude, &rhs.magnitude),
+            (true, true) => cmp_magnitudes(&rhs.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl cmp::PartialOrd for BigInt {
+    fn partial_cmp(&self, rhs: &BigInt) -> Option<cmp::Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // split the magnitude into base 10^9 digits, most significant
+        // last
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (quotient, chunk) = divrem_small(&magnitude, 1_000_000_000);
+            chunks.push(chunk);
+            magnitude = quotient;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn arithmetic() {
+        let big = BigInt::parse(b"123456789012345678901234567890").unwrap();
+        assert_eq!(big.to_string(), "123456789012345678901234567890");
+        let squared = &big * &big;
+        assert_eq!(
+            squared.to_string(),
+            "15241578753238836750495351562536198787501905199875019052100"
+        );
+        let (quotient, remainder) = squared.divrem(&(&big + &BigInt::from(1))).unwrap();
+        assert_eq!(quotient.to_string(), "123456789012345678901234567889");
+        assert_eq!(remainder.to_string(), "1");
+        assert_eq!((&BigInt::from(5) - &big).to_string(), "-123456789012345678901234567885");
+        assert_eq!((&big - &big).to_i32(), Some(0));
+        assert!(BigInt::from(-3) < BigInt::from(2));
+        assert_eq!(BigInt::from(::std::i32::MIN).to_i32(), Some(::std::i32::MIN));
+        assert_eq!(BigInt::from_f64(1e30).unwrap().to_string(), "1000000000000000019884624838656");
+        assert_eq!(BigInt::from(-12).gcd(&BigInt::from(18)), BigInt::from(6));
+        assert_eq!(BigInt::from(3).shl(70).to_string(), "3541774862152233910272");
+    }
+}
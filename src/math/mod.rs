@@ -1,8 +1,31 @@
 use types::*;
 
+pub mod bigint;
+
 pub mod math_builtins {
     use builtins::*;
+    use lisp;
+    use lisp::allocate::AllocObject;
+    use symbols_table::SymbolLookup;
     use super::*;
+
+    // a `division-by-zero` error for dividing `num` by zero, which
+    // can't be done exactly, or taking its remainder by zero, which as
+    // an integer would panic and as a float would be NaN
+    fn division_by_zero(l: &mut lisp::Lisp, num: RlispNum) -> Object {
+        let kind = Object::from(l.make_symbol(b"division-by-zero"));
+        let num = l.alloc_num(num);
+        l.alloc(RlispError::custom(kind, num))
+    }
+
+    // a `wrong-type-error` for passing the non-rational `n` to a
+    // function which wants a rational
+    fn not_a_rational(l: &mut lisp::Lisp, n: Object) -> Object {
+        let e = RlispError::wrong_type(l.type_name(RlispType::Rational),
+                                       l.type_name(n.deref_place().what_type()));
+        l.alloc(e)
+    }
+
     pub fn make_builtins() -> RlispBuiltins {
         builtin_functions!{
             l = lisp;
@@ -41,7 +64,7 @@ pub mod math_builtins {
                         result *= el;
                     }
 
-                    result.into()
+                    l.alloc_num(result)
                 }
             },
 
@@ -57,7 +80,7 @@ pub mod math_builtins {
                         let el = into_type_or_error!(l : el => RlispNum);
                         result += el;
                     }
-                    result.into()
+                    l.alloc_num(result)
                 }
             },
 
@@ -68,7 +91,7 @@ pub mod math_builtins {
                     let nums = into_type_or_error!(l : nums => &ConsCell);
                     let mut num = into_type_or_error!(l : nums.car => RlispNum);
                     if nums.cdr.nilp() {
-                        Object::from(-num)
+                        l.alloc_num(-num)
                     } else {
                         let rest = into_type_or_error!(l : nums.cdr => &ConsCell);
                         #[cfg_attr(feature = "cargo-clippy", allow(explicit_iter_loop))]
@@ -76,7 +99,7 @@ pub mod math_builtins {
                             let el = into_type_or_error!(l : el => RlispNum);
                             num -= el;
                         }
-                        num.into()
+                        l.alloc_num(num)
                     }
                 }
             },
@@ -89,15 +112,21 @@ pub mod math_builtins {
                     let mut num = into_type_or_error!(l: nums.car => RlispNum);
 
                     if nums.cdr.nilp() {
-                        Object::from(RlispNum::from(1) / num)
+                        if num.is_rational() && num == RlispNum::from(0) {
+                            return division_by_zero(l, RlispNum::from(1));
+                        }
+                        l.alloc_num(RlispNum::from(1) / num)
                     } else {
                         let rest = into_type_or_error!(l : nums.cdr => &ConsCell);
                         #[cfg_attr(feature = "cargo-clippy", allow(explicit_iter_loop))]
                         for el in rest {
                             let el = into_type_or_error!(l : el => RlispNum);
+                            if num.is_rational() && el.is_rational() && el == RlispNum::from(0) {
+                                return division_by_zero(l, num);
+                            }
                             num /= el;
                         }
-                        num.into()
+                        l.alloc_num(num)
                     }
                 }
             },
@@ -124,43 +153,73 @@ pub mod math_builtins {
             "rem" (num divisor) -> {
                 let num = into_type_or_error!(l : num => RlispNum);
                 let divisor = into_type_or_error!(l : divisor => RlispNum);
-                (num % divisor).into()
+                if divisor == RlispNum::from(0) {
+                    return division_by_zero(l, num);
+                }
+                l.alloc_num(num % divisor)
             },
             "mod" (num modulus) -> {
                 let mut num = into_type_or_error!(l : num => RlispNum);
                 let modulus = into_type_or_error!(l : modulus => RlispNum);
+                if modulus == RlispNum::from(0) {
+                    return division_by_zero(l, num);
+                }
                 if num < RlispNum::from(1) {
                     num *= RlispNum::from(-1);
                 }
-                (num % modulus).into()
+                l.alloc_num(num % modulus)
             },
             "trunc" (num) -> {
                 let num = into_type_or_error!(l : num => RlispNum);
                 let num = num.trunc();
-                num.into()
+                l.alloc_num(num)
             },
             "floor" (num) -> {
                 let num = into_type_or_error!(l : num => RlispNum);
                 let num = num.floor();
-                num.into()
+                l.alloc_num(num)
             },
             "ceil" (num) -> {
                 let num = into_type_or_error!(l : num => RlispNum);
                 let num = num.ceil();
-                num.into()
+                l.alloc_num(num)
             },
             "round" (num) -> {
                 let num = into_type_or_error!(l : num => RlispNum);
                 let num = num.round();
-                num.into()
+                l.alloc_num(num)
             },
             "flatten" (num) -> {
                 let num = into_type_or_error!(l : num => RlispNum);
-                num.try_flatten().into()
+                l.alloc_num(num.try_flatten())
             },
             "abs" (n) -> {
                 let num = into_type_or_error!(l : n => RlispNum);
-                num.abs().into()
+                l.alloc_num(num.abs())
+            },
+            "numerator" (n) -> {
+                match into_type_or_error!(l : n => RlispNum) {
+                    RlispNum::Ratio(numerator, _) => l.alloc_num(RlispNum::from(numerator)),
+                    RlispNum::Float(_) => not_a_rational(l, n),
+                    num => l.alloc_num(num),
+                }
+            },
+            "denominator" (n) -> {
+                match into_type_or_error!(l : n => RlispNum) {
+                    RlispNum::Ratio(_, denominator) => l.alloc_num(RlispNum::from(denominator)),
+                    RlispNum::Float(_) => not_a_rational(l, n),
+                    _ => Object::from(1),
+                }
+            },
+            "rational" (n) -> {
+                // the exact value of `n`, which for a float is the ratio
+                // it represents
+                let num = into_type_or_error!(l : n => RlispNum);
+                if let Some(rational) = num.rational() {
+                    l.alloc_num(rational)
+                } else {
+                    not_a_rational(l, n)
+                }
             },
         }
     }
@@ -10,6 +10,7 @@ use result::*;
 use std::iter::{Iterator, Peekable};
 use types::*;
 use lisp::allocate::AllocObject;
+use lisp::{SourcePos, SourceTracking};
 
 const WHITESPACE: &[u8] = &[b' ', b'\t', b'\n'];
 const COMMENT_DESIGNATORS: &[u8] = &[b';'];
@@ -17,19 +18,30 @@ const COMMENT_ENDS: &[u8] = &[b'\n'];
 
 mod numbers_and_syms;
 use self::numbers_and_syms::ReadNumsAndSyms;
+pub use self::numbers_and_syms::parse_number;
 
-fn next<V: Iterator<Item = u8>>(input: &mut Peekable<V>) -> Option<u8> {
+fn consume<V: Iterator<Item = u8>>(input: &mut Peekable<V>, pos: &mut SourcePos) -> Option<u8> {
+    // every byte the reader takes from `input` goes through this
+    // method, so that `pos` stays in step with it
+    let byte = input.next();
+    if let Some(byte) = byte {
+        pos.advance(byte);
+    }
+    byte
+}
+
+fn next<V: Iterator<Item = u8>>(input: &mut Peekable<V>, pos: &mut SourcePos) -> Option<u8> {
     // this method skips past comments, which it does by checking if each
     // new peek'd character is in `COMMENT_DESIGNATORS`, and then looping
     // until it hits a member of `COMMENT_ENDS`
-    match input.next() {
+    match consume(input, pos) {
         Some(next) if COMMENT_DESIGNATORS.contains(&next) => loop {
-            match input.next() {
+            match consume(input, pos) {
                 None => {
                     return None;
                 }
                 Some(next) if COMMENT_ENDS.contains(&next) => {
-                    return input.next();
+                    return consume(input, pos);
                 }
                 Some(_) => {
                     continue;
@@ -40,21 +52,21 @@ fn next<V: Iterator<Item = u8>>(input: &mut Peekable<V>) -> Option<u8> {
     }
 }
 
-fn peek<V: Iterator<Item = u8>>(input: &mut Peekable<V>) -> Option<u8> {
+fn peek<V: Iterator<Item = u8>>(input: &mut Peekable<V>, pos: &mut SourcePos) -> Option<u8> {
     // this method skips past comments, which it does by checking if each
     // new peek'd character is in `COMMENT_DESIGNATORS`, and then looping
     // until it hits a member of `COMMENT_ENDS`
 
     match peek_without_check_comment(input) {
         Some(p) if COMMENT_DESIGNATORS.contains(&p) => {
-            let _ = input.next();
+            let _ = consume(input, pos);
             loop {
-                match input.next() {
+                match consume(input, pos) {
                     None => {
                         return None;
                     }
                     Some(next) if COMMENT_ENDS.contains(&next) => {
-                        return peek(input);
+                        return peek(input, pos);
                     }
                     Some(_) => {
                         continue;
@@ -83,19 +95,46 @@ where
     }
 }
 
+fn locate_error(err: Error, file: Option<::std::rc::Rc<str>>, start: SourcePos) -> Error {
+    // Errors met while reading a named file are chained onto an
+    // `AtSource` error giving the position of the innermost list or
+    // string which was being read.
+    match (file, err.kind()) {
+        (_, &ErrorKind::AtSource(_)) | (None, _) => err,
+        (Some(file), _) => {
+            let location = format!("{}:{}:{}", file, start.line, start.col);
+            Error::with_chain(err, ErrorKind::AtSource(location))
+        }
+    }
+}
+
 pub trait Reader
-    : lisp::MacroChars + AllocObject + list::ListOps + ReadNumsAndSyms {
+    : lisp::MacroChars + AllocObject + list::ListOps + ReadNumsAndSyms + SourceTracking {
     fn read<V: Iterator<Item = u8>>(&mut self, input: &mut Peekable<V>) -> Result<Option<Object>> {
         debug!("called read()");
         // This is the function called by `Rep`.  Passed an &mut
         // Peekable<Iterator<Item = u8>>, it consumes the text
         // representing the first Rlisp object and returns that
         // object. Ok(None) signals that the iterator is empty (EOF).
-        if let Some(p) = peek(input) {
+        if let Some(p) = peek(input, self.source_pos()) {
             if let Some(symbol) = self.check_macro_char(p) {
-                let _ = next(input);
+                let start = *self.source_pos();
+                let _ = next(input, self.source_pos());
+                let symbol = match peek_without_check_comment(input) {
+                    Some(second) => {
+                        if let Some(pair) = self.check_macro_char_pair(p, second) {
+                            let _ = consume(input, self.source_pos());
+                            pair
+                        } else {
+                            symbol
+                        }
+                    }
+                    None => symbol,
+                };
                 if let Some(obj) = self.read(input)? {
-                    Ok(Some(self.list_from_vec(vec![symbol, obj])))
+                    let list = self.list_from_vec(vec![symbol, obj]);
+                    self.record_span(list, start);
+                    Ok(Some(list))
                 } else {
                     Err(ErrorKind::UnexpectedEOF.into())
                 }
@@ -111,19 +150,30 @@ pub trait Reader
         &mut self,
         iter: &mut Peekable<V>,
     ) -> Result<Option<Object>> {
-        // Some chars ('\'', '`', ',') denote macros, which are
-        // expanded at read-time into calls to `quote`, `backquote`
-        // and `comma`. `read_from_char` checks those, and then calls
-        // this function if it does not find a match.
-        if let Some(p) = peek(iter) {
+        // Some chars ('\'', '`', ',', ",@") denote macros, which are
+        // expanded at read-time into calls to `quote`, `backquote`,
+        // `comma` and `comma-at`. `read_from_char` checks those, and
+        // then calls this function if it does not find a match.
+        if let Some(p) = peek(iter, self.source_pos()) {
+            let start = *self.source_pos();
             match p {
                 b'(' => {
-                    let _ = next(iter);
-                    Ok(Some(self.read_list(iter)?))
+                    let _ = next(iter, self.source_pos());
+                    let list = self.read_list(iter)
+                        .map_err(|e| locate_error(e, self.source_file(), start))?;
+                    self.record_span(list, start);
+                    Ok(Some(list))
                 }
-                b'"' => Ok(Some(self.read_string(iter)?)),
+                b'#' => {
+                    let _ = next(iter, self.source_pos());
+                    let obj = self.read_dispatch(iter)
+                        .map_err(|e| locate_error(e, self.source_file(), start))?;
+                    Ok(Some(obj))
+                }
+                b'"' => Ok(Some(self.read_string(iter)
+                    .map_err(|e| locate_error(e, self.source_file(), start))?)),
                 _ if WHITESPACE.contains(&p) => {
-                    let _ = next(iter);
+                    let _ = next(iter, self.source_pos());
                     self.read(iter)
                 }
                 _ => self.read_symbol_or_number(iter),
@@ -133,14 +183,68 @@ pub trait Reader
         }
     }
 
-    #[cfg_attr(feature = "cargo-clippy", allow(while_let_on_iterator))]
     fn read_list<V: Iterator<Item = u8>>(&mut self, iter: &mut Peekable<V>) -> Result<Object> {
+        let elems = self.read_elements(iter)?;
+        Ok(self.list_from_vec(elems))
+    }
+
+    fn read_dispatch<V: Iterator<Item = u8>>(&mut self, iter: &mut Peekable<V>) -> Result<Object> {
+        // `#` has already been consumed; the char after it says what
+        // kind of object follows
+        match consume(iter, self.source_pos()) {
+            Some(b'(') => {
+                let elems = self.read_elements(iter)?;
+                Ok(self.alloc(RlispVector::from(elems)))
+            }
+            Some(b'\\') => self.read_char(iter),
+            Some(other) => Err(ErrorKind::UnknownDispatch(char::from(other)).into()),
+            None => Err(ErrorKind::UnexpectedEOF.into()),
+        }
+    }
+
+    fn read_char<V: Iterator<Item = u8>>(&mut self, iter: &mut Peekable<V>) -> Result<Object> {
+        // `#\` has already been consumed. What follows is either a
+        // single (UTF-8 encoded) char, or the name of one, like
+        // `space`. The first byte is always part of it, so that `#\)`
+        // and `#\;` work.
+        let mut bytes = if let Some(first) = consume(iter, self.source_pos()) {
+            vec![first]
+        } else {
+            return Err(ErrorKind::UnexpectedEOF.into());
+        };
+        while let Some(p) = peek_without_check_comment(iter) {
+            if p == b')' || WHITESPACE.contains(&p) || COMMENT_DESIGNATORS.contains(&p) {
+                break;
+            }
+            bytes.push(consume(iter, self.source_pos()).unwrap());
+        }
+        let name = ::std::str::from_utf8(&bytes)?;
+        let mut chars = name.chars();
+        let c = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => match name.to_lowercase().as_ref() {
+                "space" => ' ',
+                "newline" => '\n',
+                "tab" => '\t',
+                _ => return Err(ErrorKind::UnknownCharName(name.to_owned()).into()),
+            },
+        };
+        Ok(Object::from(c))
+    }
+
+    #[cfg_attr(feature = "cargo-clippy", allow(while_let_on_iterator))]
+    fn read_elements<V: Iterator<Item = u8>>(
+        &mut self,
+        iter: &mut Peekable<V>,
+    ) -> Result<Vec<Object>> {
+        // reads objects up to and including a closing paren. The
+        // opening paren has already been consumed.
         let mut elems = Vec::new();
-        while let Some(p) = peek(iter) {
+        while let Some(p) = peek(iter, self.source_pos()) {
             match p {
                 b')' => {
-                    let _ = next(iter);
-                    return Ok(self.list_from_vec(elems));
+                    let _ = next(iter, self.source_pos());
+                    return Ok(elems);
                 }
                 _ => {
                     if let Some(el) = self.read(iter)? {
@@ -198,19 +302,19 @@ pub trait Reader
         // have more characters open strings: if `%` opens a string
         // (it doesn't, but imagine it did), `"` shouldn't close it,
         // and vice versa.
-        if let Some(open) = iter.next() {
+        if let Some(open) = consume(iter, self.source_pos()) {
             let mut string = Vec::new();
 
-            // this method calls `iter.next()` instead of
+            // this method calls `consume(iter)` instead of
             // `next(iter)` because strings do not skip
             // comments.
-            while let Some(byte) = iter.next() {
+            while let Some(byte) = consume(iter, self.source_pos()) {
                 match byte {
                     _ if byte == open => {
                         return Ok(self.alloc_string(::std::str::from_utf8(&string)?));
                     }
                     b'\\' => {
-                        if let Some(escape) = iter.next() {
+                        if let Some(escape) = consume(iter, self.source_pos()) {
                             match escape {
                                 b't' => string.push(b'\t'),
                                 b'n' => string.push(b'\n'),
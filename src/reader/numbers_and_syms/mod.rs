@@ -1,8 +1,10 @@
 use std::str::{FromStr, from_utf8_unchecked};
 use lisp;
-use super::{peek, WHITESPACE};
+use super::{consume, peek, WHITESPACE};
+use lisp::SourceTracking;
 use std::iter::{Iterator, Peekable};
-use types::Object;
+use types::{Object, RlispNum};
+use math::bigint::BigInt;
 
 #[cfg_attr(feature = "cargo-clippy",
            allow(if_same_then_else, needless_pass_by_value, transmute_int_to_float, float_cmp,
@@ -12,7 +14,10 @@ mod copied_from_libcore;
 use self::copied_from_libcore::parse::{parse_decimal, ParseResult, Sign};
 use self::copied_from_libcore::{convert, extract_sign};
 
-fn parse_number(s: &[u8]) -> Option<Object> {
+/// the number `s` denotes, if it is one. Integers too big for an
+/// `i32` are read as `RlispNum::Big`s, and ratios like `1/3` as
+/// `RlispNum::Ratio`s.
+pub fn parse_number(s: &[u8]) -> Option<RlispNum> {
     if s.is_empty() {
         return None;
     }
@@ -21,21 +26,36 @@ fn parse_number(s: &[u8]) -> Option<Object> {
         return None;
     }
 
+    if let Some(slash) = s.iter().position(|&c| c == b'/') {
+        // a ratio, which is only a number if both sides are digits
+        // and the denominator isn't zero
+        let numerator = BigInt::parse(&s[..slash])?;
+        let denominator = BigInt::parse(&s[slash + 1..])?;
+        let numerator = match sign {
+            Sign::Positive => numerator,
+            Sign::Negative => -&numerator,
+        };
+        return RlispNum::ratio(&numerator, &denominator);
+    }
+
     let flt = match parse_decimal(s) {
         ParseResult::Valid(decimal) => {
             if decimal.fractional.is_empty() && decimal.exp == 0 {
-                let int = i32::from_str(unsafe { from_utf8_unchecked(decimal.integral) }).unwrap();
-                return Some(Object::from(match sign {
+                let int = match i32::from_str(unsafe { from_utf8_unchecked(decimal.integral) }) {
+                    Ok(int) => RlispNum::from(int),
+                    Err(_) => RlispNum::from(BigInt::parse(decimal.integral)?),
+                };
+                return Some(match sign {
                     Sign::Positive => int,
                     Sign::Negative => -int,
-                }));
+                });
             } else {
                 convert(decimal).unwrap()
             }
         }
         ParseResult::ShortcutToInf => ::std::f64::INFINITY,
         ParseResult::ShortcutToZero => {
-            return Some(Object::from(0i32));
+            return Some(RlispNum::from(0));
         }
         ParseResult::Invalid => {
             return None;
@@ -43,13 +63,13 @@ fn parse_number(s: &[u8]) -> Option<Object> {
     };
 
     match sign {
-        Sign::Positive => Some(Object::from(flt)),
-        Sign::Negative => Some(Object::from(-flt)),
+        Sign::Positive => Some(RlispNum::from(flt)),
+        Sign::Negative => Some(RlispNum::from(-flt)),
     }
 }
 
 pub trait ReadNumsAndSyms
-    : lisp::allocate::AllocObject + ::symbols_table::SymbolLookup {
+    : lisp::allocate::AllocObject + ::symbols_table::SymbolLookup + SourceTracking {
     fn read_symbol_or_number<V>(
         &mut self,
         iter: &mut Peekable<V>,
@@ -57,10 +77,10 @@ pub trait ReadNumsAndSyms
     where
         V: Iterator<Item = u8>,
     {
-        if let Some(p) = peek(iter) {
+        if let Some(p) = peek(iter, self.source_pos()) {
             let mut sym = vec![p];
-            let _ = iter.next();
-            while let Some(p) = peek(iter) {
+            let _ = consume(iter, self.source_pos());
+            while let Some(p) = peek(iter, self.source_pos()) {
                 match p {
                     b')' => {
                         return Ok(Some(self.finish_symbol_or_number(sym)));
@@ -69,7 +89,7 @@ pub trait ReadNumsAndSyms
                         return Ok(Some(self.finish_symbol_or_number(sym)));
                     }
                     _ => {
-                        sym.push(iter.next().unwrap());
+                        sym.push(consume(iter, self.source_pos()).unwrap());
                     }
                 }
             }
@@ -80,8 +100,8 @@ pub trait ReadNumsAndSyms
     }
 
     fn finish_symbol_or_number(&mut self, sym: Vec<u8>) -> Object {
-        if let Some(object) = parse_number(&sym) {
-            object
+        if let Some(num) = parse_number(&sym) {
+            self.alloc_num(num)
         } else {
             Object::from(self.make_symbol(sym.as_ref()))
         }
@@ -9,6 +9,13 @@ pub mod stdio;
 pub mod string_repl;
 pub mod vec_repl;
 
+/// Formats `err` along with the errors which caused it, e.g. "at
+/// foo.rlsp:3:1: met EOF before a list was closed"
+pub fn error_message(err: &Error) -> String {
+    let messages: Vec<String> = err.iter().map(|e| e.to_string()).collect();
+    messages.join(": ")
+}
+
 pub trait Repl<R, L>
 where
     R: convert::AsMut<L>,
@@ -48,7 +55,21 @@ pub trait Rep: ::reader::Reader + ::evaluator::Evaluator {
         res
     }
     fn print(&self, evaled: Object) -> String {
-        format!("{}", evaled)
+        // errors are followed by the functions they were signaled
+        // in, innermost first
+        let mut out = format!("{}", evaled);
+        if let Some(e) = <&RlispError as conversions::MaybeFrom<Object>>::maybe_from(evaled) {
+            if let Some(ref backtrace) = e.backtrace {
+                for name in backtrace {
+                    if name.nilp() {
+                        out.push_str("\n  in anonymous function");
+                    } else {
+                        out.push_str(&format!("\n  in {}", name));
+                    }
+                }
+            }
+        }
+        out
     }
     fn rep<V: Iterator<Item = u8>>(&mut self, input: &mut Peekable<V>) -> Result<Option<String>> {
         let read = <Self as Rep>::read(self, input)?;
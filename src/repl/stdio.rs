@@ -1,7 +1,7 @@
 use result::*;
 use std::io;
 use std::io::prelude::*;
-use super::{Rep, Repl};
+use super::{error_message, Rep, Repl};
 use lisp::Lisp;
 use std::iter::Iterator;
 use std::convert;
@@ -52,7 +52,7 @@ impl<R: convert::AsMut<Lisp>> Repl<R, Lisp> for StdIoRepl<R> {
         Ok(())
     }
     fn write_error(err: Error, error: &mut Self::Error) -> Result<()> {
-        write!(error, "ERROR: {}\n", err)?;
+        write!(error, "ERROR: {}\n", error_message(&err))?;
         error.flush()?;
         Ok(())
     }
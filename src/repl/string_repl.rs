@@ -63,22 +63,993 @@ impl Default for StringRepl<boxed::Box<Lisp>> {
 mod test {
     use super::*;
     use repl::Repl;
-    #[test]
-    fn one_plus_one() {
-        let mut input = String::from("(+ 1 1)");
+
+    // runs `input` and returns the output of its last form, checking
+    // that it left nothing on the stack and no scope open
+    fn run(lisp: &mut Lisp, input: &str) -> String {
+        let mut input = String::from(input);
         let mut output = String::new();
         let mut error = String::new();
-        let mut repl = StringRepl::default();
-        repl.run(&mut input, &mut output, &mut error).unwrap();
-        assert_eq!(output, "2");
+        StringRepl::from(&mut *lisp)
+            .run(&mut input, &mut output, &mut error)
+            .unwrap();
+        assert!(lisp.stack.is_empty());
+        assert_eq!(lisp.symbols.len(), 1);
+        output
+    }
+
+    #[test]
+    fn one_plus_one() {
+        let mut lisp = Lisp::default();
+        assert_eq!(run(&mut lisp, "(+ 1 1)"), "2");
     }
     #[test]
     fn multiple_in_sequence() {
-        let mut input = String::from("(defvar x 1) (setq x (+ x x)) x");
+        let mut lisp = Lisp::default();
+        assert_eq!(run(&mut lisp, "(defvar x 1) (setq x (+ x x)) x"), "2");
+    }
+    #[test]
+    fn macros() {
+        let mut lisp = Lisp::default();
+        assert_eq!(
+            run(&mut lisp, "(defmacro twice (x) (list '+ x x)) (twice 3)"),
+            "6"
+        );
+    }
+    #[test]
+    fn macroexpansion() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defmacro twice (x) (list '+ x x)) \
+             (defmacro twice-twice (x) (list 'twice (list 'twice x)))",
+        );
+        assert_eq!(
+            run(&mut lisp, "(macroexpand-1 '(twice-twice 3))"),
+            "(twice (twice 3))"
+        );
+        assert_eq!(
+            run(&mut lisp, "(macroexpand '(twice-twice 3))"),
+            "(+ (twice 3) (twice 3))"
+        );
+    }
+    #[test]
+    fn quasiquote() {
+        let mut lisp = Lisp::default();
+        run(&mut lisp, "(defvar x 1) (defvar xs (list 2 3))");
+        assert_eq!(run(&mut lisp, "`(0 ,x 4)"), "(0 1 4)");
+        assert_eq!(run(&mut lisp, "`(0 ,@xs 4)"), "(0 2 3 4)");
+        assert_eq!(run(&mut lisp, "`(0 (x ,@xs))"), "(0 (x 2 3))");
+        assert_eq!(
+            run(&mut lisp, "`(0 `(a ,(b ,x)))"),
+            "(0 (backquote (a (comma (b 1)))))"
+        );
+    }
+    #[test]
+    fn tail_calls() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun count-down (n) \
+               (cond ((= n 0) 'done) \
+                     ((= n n) (let ((m (- n 1))) (progn (count-down m))))))",
+        );
+        assert_eq!(run(&mut lisp, "(count-down 10000)"), "done");
+    }
+    #[test]
+    fn inner_bindings_shadow_outer_ones() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defvar n 'global) \
+             (defun own (n) n) \
+             (defun one (n) 1) \
+             (defun pair (n) (list n (one 0))) \
+             (defun fact (n) (cond ((= n 0) 1) ((= n n) (* n (fact (- n 1))))))",
+        );
+        assert_eq!(run(&mut lisp, "(own 3)"), "3");
+        assert_eq!(run(&mut lisp, "(let ((n 4)) (let ((n 5)) n))"), "5");
+        assert_eq!(run(&mut lisp, "(pair 2)"), "(2 1)");
+        assert_eq!(run(&mut lisp, "(fact 5)"), "120");
+        assert_eq!(run(&mut lisp, "n"), "global");
+    }
+    #[test]
+    fn stack_depth_exceeded() {
+        let mut lisp = Lisp::default();
+        lisp.max_eval_depth = 100;
+        run(&mut lisp, "(defun forever (n) (+ 1 (forever n)))");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (forever 0) (stack-depth-exceeded-error 'caught))"
+            ),
+            "caught"
+        );
+        assert_eq!(lisp.eval_depth, 0);
+    }
+    #[test]
+    fn max_eval_depth_is_settable() {
+        let mut lisp = Lisp::default();
+        run(&mut lisp, "(defun forever (n) (+ 1 (forever n)))");
+        assert_eq!(run(&mut lisp, "(max-eval-depth)"), "1000");
+        assert_eq!(run(&mut lisp, "(max-eval-depth 50)"), "50");
+        assert_eq!(run(&mut lisp, "(max-eval-depth)"), "50");
+        assert_eq!(lisp.max_eval_depth, 50);
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (forever 0) (stack-depth-exceeded-error (e) (error-info e)))"
+            ),
+            "(50)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (max-eval-depth 0) (eval-depth-error 'bad))"
+            ),
+            "bad"
+        );
+        assert_eq!(run(&mut lisp, "(max-eval-depth)"), "50");
+    }
+    #[test]
+    fn stack_depth_exceeded_at_default_depth() {
+        // the default depth must be reached before the Rust stack of
+        // the thread started by `on_eval_stack` overflows, even when
+        // each level calls back into lisp from a builtin
+        let output = ::lisp::on_eval_stack(|| {
+            let mut lisp = Lisp::default();
+            run(
+                &mut lisp,
+                "(defun forever (n) (+ 1 (car (mapcar 'forever (list n)))))",
+            );
+            run(
+                &mut lisp,
+                "(catch-error (forever 0) (stack-depth-exceeded-error 'caught))",
+            )
+        });
+        assert_eq!(output, "caught");
+    }
+    #[test]
+    fn backtraces() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun inner (x) (list (+ x 'a))) \
+             (defun middle (x) (list (inner x))) \
+             (defun outer (x) (middle x))",
+        );
+        // `middle` is called in tail position, so `outer` has
+        // already returned
+        assert_eq!(
+            run(&mut lisp, "(catch-error (outer 1) (t (e) (error-backtrace e)))"),
+            "(+ inner middle)"
+        );
+        assert!(run(&mut lisp, "(outer 1)").ends_with("\n  in +\n  in inner\n  in middle"));
+    }
+    #[test]
+    fn source_locations() {
+        let mut lisp = Lisp::default();
+        lisp.begin_source("test.rlsp");
+        assert!(
+            run(&mut lisp, "(defun f (x)\n  (+ x 'a))\n(f 1)")
+                .starts_with("ERROR: test.rlsp:2:3: expected type number but found type symbol")
+        );
+
+        lisp.begin_source("test.rlsp");
+        let mut input = String::from("(f 1)\n (list 1\n  \"two\"");
         let mut output = String::new();
         let mut error = String::new();
-        let mut repl = StringRepl::default();
-        repl.run(&mut input, &mut output, &mut error).unwrap();
-        assert_eq!(output, "2");
+        let err = StringRepl::from(&mut lisp)
+            .run(&mut input, &mut output, &mut error)
+            .unwrap_err();
+        assert_eq!(
+            ::repl::error_message(&err),
+            "at test.rlsp:2:2: met EOF before a list was closed"
+        );
+    }
+    #[test]
+    fn conditions_and_restarts() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun parse-record (r) \
+               (restart-case (if (eq r 'bad) (error 'bad-record r) r) \
+                 (use-value (v) v)))",
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(handler-bind ((bad-record (lambda (e) (invoke-restart 'use-value 0)))) \
+                   (list (parse-record 1) (parse-record 'bad) (parse-record 3)))"
+            ),
+            "(1 0 3)"
+        );
+
+        // handlers which return normally decline to handle the error
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(handler-case \
+                   (handler-bind ((t (lambda (e) 'declined))) (parse-record 'bad)) \
+                   (bad-record (e) 'caught))"
+            ),
+            "caught"
+        );
+
+        // handlers may be named by variables
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(defvar use-zero (lambda (e) (invoke-restart 'use-value 0))) \
+                 (handler-bind ((bad-record use-zero)) (parse-record 'bad))"
+            ),
+            "0"
+        );
+
+        // restarts unwind through catch-error
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(restart-case (catch-error (invoke-restart 'skip 1) (t 'wrong)) \
+                   (skip (&optional n) (list 'skipped n)))"
+            ),
+            "(skipped 1)"
+        );
+
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (invoke-restart 'nope) (control-error 'none))"
+            ),
+            "none"
+        );
+    }
+    #[test]
+    fn error_accessors() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun describe (e) (list (error-kind e) (error-info e) (error-message e)))",
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (error 'bad-record 1 2) (bad-record (e) (describe e)))"
+            ),
+            "(bad-record (1 2) bad-record: (1 2))"
+        );
+        assert_eq!(
+            run(&mut lisp, "(catch-error (+ 1 'a) (t (e) (describe e)))"),
+            "(wrong-type-error (number symbol) expected type number but found type symbol)"
+        );
+    }
+    #[test]
+    fn unwind_protect() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defvar log nil) \
+             (defun risky () (let ((x 1)) (unwind-protect (error 'boom x) (setq log 'cleaned))))",
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(let ((r (catch-error (risky) (boom 'caught)))) (list r log))"
+            ),
+            "(caught cleaned)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(restart-case (unwind-protect (invoke-restart 'skip) (setq log 'unwound)) \
+                   (skip () (list (unwind-protect 1 2) log)))"
+            ),
+            "(1 unwound)"
+        );
+    }
+    #[test]
+    fn non_local_exits() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun walk (xs target k) \
+               (if (eq xs nil) nil \
+                 (if (eq (car xs) target) (k (car xs)) (walk (cdr xs) target k)))) \
+             (defun deep (n) (if (= n 0) (throw 'done 'bottom) (list (deep (- n 1))))) \
+             (defvar stale (block b (lambda () (return-from b 1))))",
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(block found \
+                   (walk (list 1 2 3) 2 (lambda (x) (return-from found (list 'found x)))) \
+                   'none)"
+            ),
+            "(found 2)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(block b (catch-error (return-from b 'out) (t 'caught)))"
+            ),
+            "out"
+        );
+        assert_eq!(run(&mut lisp, "(catch 'done (deep 5))"), "bottom");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (throw 'nope 1) (control-error (e) (error-info e)))"
+            ),
+            "(catch-tag nope)"
+        );
+        assert_eq!(
+            run(&mut lisp, "(catch-error (stale) (control-error 'stale))"),
+            "stale"
+        );
+    }
+    #[test]
+    fn iteration() {
+        let mut lisp = Lisp::default();
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(defvar i 0) \
+                 (defvar acc nil) \
+                 (while (< i 3) (setq acc (cons i acc)) (setq i (+ i 1))) \
+                 acc"
+            ),
+            "(2 1 0)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(let ((acc nil)) (dotimes (j 4) (setq acc (cons j acc))) acc)"
+            ),
+            "(3 2 1 0)"
+        );
+        assert_eq!(run(&mut lisp, "(dotimes (j 3 j))"), "3");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(let ((n 0)) (dolist (x (list 1 2 3) n) (setq n (+ n x))))"
+            ),
+            "6"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (dolist (x (list 1 'a 2)) (+ x 1)) (wrong-type-error 'stopped))"
+            ),
+            "stopped"
+        );
+    }
+    #[test]
+    fn loop_clauses() {
+        let mut lisp = Lisp::default();
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(loop for x in (list 1 2 3 4) for k from 10 to 100 by 10 \
+                       when (< x 4) collect (+ x k))"
+            ),
+            "(11 22 33)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(loop for x from 1 to 10 when (eq (rem x 2) 0) sum x)"
+            ),
+            "30"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(let ((total 0)) \
+                   (loop for x in (list 1 2) do (setq total (+ total x)) \
+                         finally (setq total (* total 10))) \
+                   total)"
+            ),
+            "30"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (loop for x on y) (loop-syntax-error 'bad-loop))"
+            ),
+            "bad-loop"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (loop for x from 0 to 10 by 0 collect x) \
+                              (loop-step-error (e) (error-info e)))"
+            ),
+            "(0)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (loop for x from 0 to 10 by -1 collect x) \
+                              (loop-step-error 'negative-step))"
+            ),
+            "negative-step"
+        );
+    }
+    #[test]
+    fn conditionals() {
+        let mut lisp = Lisp::default();
+        assert_eq!(run(&mut lisp, "(and)"), "t");
+        assert_eq!(run(&mut lisp, "(and 1 2)"), "2");
+        assert_eq!(run(&mut lisp, "(and 1 nil (+ 1 'a))"), "nil");
+        assert_eq!(run(&mut lisp, "(or)"), "nil");
+        assert_eq!(run(&mut lisp, "(or nil 2 (+ 1 'a))"), "2");
+        assert_eq!(run(&mut lisp, "(or nil nil)"), "nil");
+        assert_eq!(run(&mut lisp, "(when t 1 2)"), "2");
+        assert_eq!(run(&mut lisp, "(when nil 1)"), "nil");
+        assert_eq!(run(&mut lisp, "(unless nil 1 2)"), "2");
+        assert_eq!(run(&mut lisp, "(unless t 1)"), "nil");
+        assert_eq!(run(&mut lisp, "(let* ((x 1) (y (+ x 1))) (list x y))"), "(1 2)");
+        assert_eq!(run(&mut lisp, "(cond (nil 1) ((+ 1 1)) (t 3))"), "2");
+        assert_eq!(run(&mut lisp, "(cond ((eq 1 2) 1) (t (+ 1 1) 3))"), "3");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (cond ((+ 1 'a) 1) (t 2)) (wrong-type-error 'bubbled))"
+            ),
+            "bubbled"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (when (+ 1 'a) 1) (wrong-type-error 'bubbled))"
+            ),
+            "bubbled"
+        );
+
+        run(
+            &mut lisp,
+            "(defun classify (x) \
+               (case x \
+                 ((1 2 3) 'small) \
+                 (big 'big) \
+                 (otherwise 'other)))",
+        );
+        assert_eq!(run(&mut lisp, "(classify 2)"), "small");
+        assert_eq!(run(&mut lisp, "(classify 'big)"), "big");
+        assert_eq!(run(&mut lisp, "(classify \"2\")"), "other");
+    }
+    #[test]
+    fn local_functions() {
+        let mut lisp = Lisp::default();
+        run(&mut lisp, "(defun twice (x) (* 2 x))");
+        // `outer` sees the global `twice`, not the one from its `flet`
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(flet ((twice (x) (+ x x x)) \
+                        (outer (x) (twice x))) \
+                   (list (twice 1) (outer 1)))"
+            ),
+            "(3 2)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(labels ((evenp (n) (if (= n 0) t (oddp (- n 1)))) \
+                          (oddp (n) (if (= n 0) nil (evenp (- n 1))))) \
+                   (list (evenp 10) (oddp 10) (evenp 1001)))"
+            ),
+            "(t nil nil)"
+        );
+        assert_eq!(run(&mut lisp, "(twice 1)"), "2");
+        assert_eq!(run(&mut lisp, "evenp"), "nil");
+    }
+    #[test]
+    fn keyword_args() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun f (a &optional (b (+ a 1) b-p) &key (c 10 c-p) d) \
+               (list a b b-p c c-p d)) \
+             (defun g (&rest r &key x) (list r x))",
+        );
+        assert_eq!(run(&mut lisp, "(f 1)"), "(1 2 nil 10 nil nil)");
+        assert_eq!(run(&mut lisp, "(f 1 5)"), "(1 5 t 10 nil nil)");
+        assert_eq!(run(&mut lisp, "(f 1 5 :d 4 :c 3)"), "(1 5 t 3 t 4)");
+        assert_eq!(run(&mut lisp, "(f 1 5 :c 3 :c 4)"), "(1 5 t 3 t nil)");
+        assert_eq!(run(&mut lisp, "(g :x 1)"), "((:x 1) 1)");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (f 1 5 :e 3) (unknown-keyword-error 'unknown))"
+            ),
+            "unknown"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (f 1 5 :c) (missing-keyword-value-error 'missing))"
+            ),
+            "missing"
+        );
+        assert_eq!(
+            run(&mut lisp, "(catch-error (f) (wrong-arg-count-error 'too-few))"),
+            "too-few"
+        );
+        assert!(
+            run(&mut lisp, "(f 1 2 :e 3)")
+                .starts_with("ERROR: found the keyword arg :e but wanted one of (:c :d)")
+        );
+    }
+    #[test]
+    fn destructuring() {
+        let mut lisp = Lisp::default();
+        run(&mut lisp, "(defun f ((a b) c) (list a b c))");
+        assert_eq!(run(&mut lisp, "(f (list 1 2) 3)"), "(1 2 3)");
+        assert_eq!(run(&mut lisp, "((lambda ((x y)) (+ x y)) (list 1 2))"), "3");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(destructuring-bind (a (b &optional (c 5)) &key d) (list 1 (list 2) :d 4) \
+                   (list a b c d))"
+            ),
+            "(1 2 5 4)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(destructuring-bind (a &rest rest) (list 1 2 3) (list a rest))"
+            ),
+            "(1 (2 3))"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (f (list 1) 3) (destructuring-error (e) (error-info e)))"
+            ),
+            "((a b) (1))"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (destructuring-bind (a b) 1 a) \
+                              (destructuring-error (e) (error-info e)))"
+            ),
+            "((a b) 1)"
+        );
+    }
+    #[test]
+    fn special_variables() {
+        let mut lisp = Lisp::default();
+        run(&mut lisp, "(defparameter *level* 1) (defun show () *level*)");
+        assert_eq!(run(&mut lisp, "(show)"), "1");
+        assert_eq!(run(&mut lisp, "(let ((*level* 2)) (show))"), "2");
+        assert_eq!(
+            run(&mut lisp, "(let* ((*level* 3) (x *level*)) (list x (show)))"),
+            "(3 3)"
+        );
+        assert_eq!(
+            run(&mut lisp, "(catch-error (let ((*level* 4)) (+ 1 'a)) (t (show)))"),
+            "1"
+        );
+        assert_eq!(
+            run(&mut lisp, "(block b (let ((*level* 5)) (return-from b (show))))"),
+            "5"
+        );
+        assert_eq!(run(&mut lisp, "(let ((*level* 6)) *level*)"), "6");
+        assert_eq!(run(&mut lisp, "(show)"), "1");
+    }
+    #[test]
+    fn defvar_is_lexical() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defvar x 3) \
+             (defvar func (let ((x 3)) (lambda () x)))",
+        );
+        assert_eq!(run(&mut lisp, "(let ((x 4)) (func))"), "3");
+    }
+    #[test]
+    fn funcall_apply_eval() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun compose (f g) (lambda (x) (funcall f (funcall g x)))) \
+             (defvar ns (make-namespace)) \
+             (set ns x 42)",
+        );
+        assert_eq!(run(&mut lisp, "(funcall + 1 2)"), "3");
+        assert_eq!(run(&mut lisp, "(funcall 'list 1 2)"), "(1 2)");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(funcall (compose (lambda (x) (* x 2)) (lambda (x) (+ x 1))) 3)"
+            ),
+            "8"
+        );
+        assert_eq!(run(&mut lisp, "(apply + 1 2 (list 3 4))"), "10");
+        assert_eq!(
+            run(&mut lisp, "(apply (lambda (x &key y) (list x y)) (list 1 :y 2))"),
+            "(1 2)"
+        );
+        assert_eq!(run(&mut lisp, "(eval (list '+ 1 2))"), "3");
+        assert_eq!(run(&mut lisp, "(let ((x 1)) (eval 'x))"), "nil");
+        assert_eq!(run(&mut lisp, "(eval '(+ x 1) ns)"), "43");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (funcall (lambda (x) x)) (wrong-arg-count-error 'arity))"
+            ),
+            "arity"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (funcall 'if t 1 2) (wrong-type-error 'special-form))"
+            ),
+            "special-form"
+        );
+    }
+
+    #[test]
+    fn list_library() {
+        let mut lisp = Lisp::default();
+        assert_eq!(run(&mut lisp, "(length '(1 2 3))"), "3");
+        assert_eq!(run(&mut lisp, "(reverse '(1 2 3))"), "(3 2 1)");
+        assert_eq!(run(&mut lisp, "(append '(1) nil '(2 3))"), "(1 2 3)");
+        assert_eq!(run(&mut lisp, "(last '(1 2 3) 2)"), "(2 3)");
+        assert_eq!(run(&mut lisp, "(butlast '(1 2 3))"), "(1 2)");
+        assert_eq!(
+            run(&mut lisp, "(mapcar (lambda (x y) (list x y)) '(1 2 3) '(a b))"),
+            "((1 a) (2 b))"
+        );
+        assert_eq!(
+            run(&mut lisp, "(reduce (lambda (a b) (- a b)) '(1 2 3) :from-end t)"),
+            "2"
+        );
+        assert_eq!(
+            run(&mut lisp, "(reduce (lambda (a b) (+ a b)) nil :initial-value 0)"),
+            "0"
+        );
+        assert_eq!(
+            run(&mut lisp, "(remove-if (lambda (x) (< x 2)) '(1 2 3))"),
+            "(2 3)"
+        );
+        assert_eq!(run(&mut lisp, "(member 2 '(1 2 3))"), "(2 3)");
+
+        run(
+            &mut lisp,
+            "(defvar pairs (list (cons 2 'a) (cons 1 'b) (cons 2 'c) (cons 1 'd)))",
+        );
+        assert_eq!(
+            run(&mut lisp, "(find 'c pairs :key (lambda (p) (cdr p)))"),
+            "(2 . c)"
+        );
+        assert_eq!(run(&mut lisp, "(assoc 1 pairs)"), "(1 . b)");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(sort pairs (lambda (a b) (< a b)) :key (lambda (p) (car p)))"
+            ),
+            "((1 . b) (1 . d) (2 . a) (2 . c))"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (sort '(1 2) (lambda (a b) (error 'oops nil))) (oops 'oops))"
+            ),
+            "oops"
+        );
+    }
+
+    #[test]
+    fn list_library_in_tail_position() {
+        // the builtins' args come from a namespace which a tail
+        // call has already left, so they must survive a gc during the
+        // callbacks
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun second-of (a b) b) \
+             (defun churn (x) (dotimes (i 2000) (second-of i (list i i))) x) \
+             (defun map-all (f xs) (mapcar f xs)) \
+             (defun sort-all (xs p) (sort xs p)) \
+             (defun reduce-all (f xs) (reduce f xs)) \
+             (defun map-hash (f h) (maphash f h))",
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(map-all (lambda (x) (churn (* x 2))) (list 1 2 3))"
+            ),
+            "(2 4 6)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(sort-all (list 3 1 2) (lambda (a b) (< (churn a) (churn b))))"
+            ),
+            "(1 2 3)"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(reduce-all (lambda (a b) (churn (+ a b))) (list 1 2 3))"
+            ),
+            "6"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(defvar h (make-hash-table)) \
+                 (defvar total 0) \
+                 (setf (gethash 'a h) 1) \
+                 (setf (gethash 'b h) 2) \
+                 (map-hash (lambda (k v) (setq total (+ total (churn v)))) h) \
+                 total"
+            ),
+            "3"
+        );
+    }
+    #[test]
+    fn vectors() {
+        let mut lisp = Lisp::default();
+        assert_eq!(run(&mut lisp, "#(1 (a b) c)"), "#(1 (a b) c)");
+
+        run(&mut lisp, "(defvar v (make-vector 2 0))");
+        assert_eq!(run(&mut lisp, "(setf (vector-ref v 0) 'y)"), "y");
+        assert_eq!(run(&mut lisp, "(vector-push v 'x)"), "2");
+        assert_eq!(run(&mut lisp, "v"), "#(y 0 x)");
+        assert_eq!(run(&mut lisp, "(vector-length v)"), "3");
+        assert_eq!(run(&mut lisp, "(vector->list v)"), "(y 0 x)");
+        assert_eq!(run(&mut lisp, "(vectorp v)"), "t");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (vector-ref v 3) (index-out-of-bounds-error 'oob))"
+            ),
+            "oob"
+        );
+
+        // a local function named `vector-ref` doesn't return a place
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(flet ((vector-ref (vector n) 'not-a-place)) \
+                   (catch-error (setf (vector-ref v 0) 'z) (wrong-type-error 'local)))"
+            ),
+            "local"
+        );
+        assert_eq!(run(&mut lisp, "(vector-ref v 0)"), "y");
+
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(defvar big (make-vector)) \
+                 (dotimes (i 1000) (vector-push big (list i))) \
+                 (vector-ref big 999)"
+            ),
+            "(999)"
+        );
+
+        // the place stays valid when the vector grows while the new
+        // value is evaluated
+        run(
+            &mut lisp,
+            "(defvar w (make-vector 1 0)) \
+             (setf (vector-ref w 0) (progn (dotimes (i 100) (vector-push w 'x)) 'set))",
+        );
+        assert_eq!(run(&mut lisp, "(vector-ref w 0)"), "set");
+        assert_eq!(run(&mut lisp, "(vector-length w)"), "101");
+    }
+
+    #[test]
+    fn hash_tables() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defvar by-eq (make-hash-table)) \
+             (setf (gethash 'a by-eq) 1)",
+        );
+        assert_eq!(run(&mut lisp, "(gethash 'a by-eq)"), "1");
+        assert_eq!(run(&mut lisp, "(gethash 'b by-eq)"), "nil");
+        assert_eq!(run(&mut lisp, "(gethash 'b by-eq 'none)"), "none");
+        assert_eq!(run(&mut lisp, "(hash-table-count by-eq)"), "1");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(defvar sum 0) \
+                 (maphash (lambda (k v) (setq sum (+ sum v))) by-eq) \
+                 sum"
+            ),
+            "1"
+        );
+
+        run(
+            &mut lisp,
+            "(defvar by-equal (make-hash-table :test 'equal)) \
+             (dotimes (i 1000) (setf (gethash (list i) by-equal) (list i i))) \
+             (setf (gethash \"a\" by-equal) 2)",
+        );
+        assert_eq!(run(&mut lisp, "(gethash \"a\" by-equal)"), "2");
+        assert_eq!(run(&mut lisp, "(gethash (list 999) by-equal)"), "(999 999)");
+        assert_eq!(run(&mut lisp, "(gethash (list 999) by-eq)"), "nil");
+        assert_eq!(run(&mut lisp, "(remhash (list 0) by-equal)"), "t");
+        assert_eq!(run(&mut lisp, "(hash-table-count by-equal)"), "1000");
+        assert_eq!(run(&mut lisp, "(equal (list 1 \"a\") (list 1 \"a\"))"), "t");
+
+        // reading a missing key doesn't add it, but setting it does,
+        // and a key set to nil is still there
+        run(
+            &mut lisp,
+            "(defvar nils (make-hash-table)) \
+             (setf (gethash 'x nils) nil) \
+             (dotimes (i 100) (gethash i nils))",
+        );
+        assert_eq!(run(&mut lisp, "(gethash 'x nils 'none)"), "nil");
+        assert_eq!(run(&mut lisp, "(setf (gethash 'y nils) 3)"), "3");
+        assert_eq!(run(&mut lisp, "(hash-table-count nils)"), "2");
+
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (setf (gethash) 1) (wrong-arg-count-error 'arity))"
+            ),
+            "arity"
+        );
+        // a local function named `gethash` doesn't return a place
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(flet ((gethash (key table) 'not-a-place)) \
+                   (catch-error (setf (gethash 'a by-eq) 9) (wrong-type-error 'local)))"
+            ),
+            "local"
+        );
+        assert_eq!(run(&mut lisp, "(gethash 'a by-eq)"), "1");
+    }
+
+    #[test]
+    fn characters() {
+        let mut lisp = Lisp::default();
+        assert_eq!(
+            run(&mut lisp, "(list #\\a #\\space #\\Newline #\\))"),
+            "(#\\a #\\space #\\newline #\\))"
+        );
+        assert_eq!(run(&mut lisp, "(characterp #\\a)"), "t");
+        assert_eq!(run(&mut lisp, "(characterp 97)"), "nil");
+        assert_eq!(run(&mut lisp, "(char-code #\\λ)"), "955");
+        assert_eq!(run(&mut lisp, "(code-char 97)"), "#\\a");
+        assert_eq!(run(&mut lisp, "(eq (code-char 97) #\\a)"), "t");
+        assert_eq!(run(&mut lisp, "(char-upcase #\\a)"), "#\\A");
+        assert_eq!(run(&mut lisp, "(char \"aλb\" 1)"), "#\\λ");
+        assert_eq!(run(&mut lisp, "(char \"aλb\" 2)"), "#\\b");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (char \"aλb\" 3) (index-out-of-bounds-error 'oob))"
+            ),
+            "oob"
+        );
+    }
+
+    #[test]
+    fn string_library() {
+        let mut lisp = Lisp::default();
+        assert_eq!(run(&mut lisp, "(string-length \"aλb\")"), "3");
+        assert_eq!(run(&mut lisp, "(substring \"aλbcd\" 1 3)"), "λb");
+        assert_eq!(run(&mut lisp, "(string-append \"a\" \"λ\" \"b\")"), "aλb");
+        assert_eq!(run(&mut lisp, "(string-split \" a  b \")"), "(a b)");
+        assert_eq!(run(&mut lisp, "(string-split \"a,b,,c\" #\\,)"), "(a b  c)");
+        assert_eq!(run(&mut lisp, "(string-join (list \"a\" \"b\") \"-\")"), "a-b");
+        assert_eq!(run(&mut lisp, "(string-trim \"xxλxx\" \"x\")"), "λ");
+        assert_eq!(run(&mut lisp, "(string-upcase \"aλ\")"), "AΛ");
+        assert_eq!(run(&mut lisp, "(string-search \"b\" \"aλbab\" 3)"), "4");
+        assert_eq!(run(&mut lisp, "(string= \"a\" \"a\")"), "t");
+        assert_eq!(run(&mut lisp, "(string< \"b\" \"a\")"), "nil");
+        assert_eq!(run(&mut lisp, "(+ 1 (string->number \"41\"))"), "42");
+        assert_eq!(run(&mut lisp, "(string->number \"x\")"), "nil");
+        assert_eq!(run(&mut lisp, "(number->string 1.5)"), "1.5");
+        assert_eq!(run(&mut lisp, "(eq (string->symbol \"foo\") 'foo)"), "t");
+        assert_eq!(run(&mut lisp, "(symbol->string 'foo)"), "foo");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (substring \"abc\" 1 4) (index-out-of-bounds-error 'oob))"
+            ),
+            "oob"
+        );
+    }
+
+    #[test]
+    fn string_builders() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defvar builder (make-string-builder \"x\")) \
+             (dotimes (i 1000) \
+               (string-builder-append (make-string-builder \"garbage\") \"y\") \
+               (string-push builder #\\λ)) \
+             (string-builder-append builder \"ab\" #\\c) \
+             (defvar built (string-builder->string builder))",
+        );
+        assert_eq!(run(&mut lisp, "(string-builder-length builder)"), "1004");
+        assert_eq!(run(&mut lisp, "(string-length built)"), "1004");
+        assert_eq!(run(&mut lisp, "(substring built 998)"), "λλλabc");
+        assert_eq!(run(&mut lisp, "(stringp built)"), "t");
+        assert_eq!(run(&mut lisp, "(string-builder-p builder)"), "t");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (string-push builder \"s\") (wrong-type-error 'wrong-type))"
+            ),
+            "wrong-type"
+        );
+    }
+    #[test]
+    fn bignums() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun fact (n) (if (< n 2) 1 (* n (fact (- n 1))))) \
+             (defvar big (fact 30)) \
+             (dotimes (i 1000) (* big big))",
+        );
+        assert_eq!(run(&mut lisp, "big"), "265252859812191058636308480000000");
+        assert_eq!(run(&mut lisp, "(+ 2147483647 1)"), "2147483648");
+        assert_eq!(run(&mut lisp, "(- -2147483648 1)"), "-2147483649");
+        assert_eq!(run(&mut lisp, "(- (+ 2147483647 1) 1)"), "2147483647");
+        assert_eq!(run(&mut lisp, "(type-of (- (+ 2147483647 1) 1))"), "integer");
+        assert_eq!(run(&mut lisp, "(integerp big)"), "t");
+        assert_eq!(run(&mut lisp, "(< (fact 20) (fact 21))"), "t");
+        assert_eq!(run(&mut lisp, "(= (fact 20) (* 20 (fact 19)))"), "t");
+        assert_eq!(run(&mut lisp, "(equal big (fact 30))"), "t");
+        assert_eq!(run(&mut lisp, "(rem big 1000007)"), "790627");
+        assert_eq!(
+            run(&mut lisp, "-123456789012345678901234567890"),
+            "-123456789012345678901234567890"
+        );
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (rem big 0) (division-by-zero 'division-by-zero))"
+            ),
+            "division-by-zero"
+        );
+    }
+    #[test]
+    fn rationals() {
+        let mut lisp = Lisp::default();
+        run(
+            &mut lisp,
+            "(defun gcd (a b) (if (= b 0) a (gcd b (rem a b)))) \
+             (defun lcm (a b) (/ (abs (* a b)) (gcd a b))) \
+             (defvar third (/ 1 3)) \
+             (dotimes (i 1000) (+ third 1/6))",
+        );
+        assert_eq!(run(&mut lisp, "third"), "1/3");
+        assert_eq!(run(&mut lisp, "(+ third 1/6)"), "1/2");
+        assert_eq!(run(&mut lisp, "(* 3 third)"), "1");
+        assert_eq!(run(&mut lisp, "-6/4"), "-3/2");
+        assert_eq!(run(&mut lisp, "(type-of 2/3)"), "ratio");
+        assert_eq!(run(&mut lisp, "(lcm 4 6)"), "12");
+        assert_eq!(run(&mut lisp, "(+ 1/2 0.25)"), "0.75");
+        assert_eq!(run(&mut lisp, "(< third 0.34)"), "t");
+        assert_eq!(run(&mut lisp, "(= 1/2 2/4)"), "t");
+        assert_eq!(run(&mut lisp, "(numerator -6/4)"), "-3");
+        assert_eq!(run(&mut lisp, "(denominator -6/4)"), "2");
+        assert_eq!(run(&mut lisp, "(rational 0.375)"), "3/8");
+        assert_eq!(run(&mut lisp, "(floor -7/2)"), "-4");
+        assert_eq!(run(&mut lisp, "(round 7/2)"), "4");
+        assert_eq!(run(&mut lisp, "(rem -7/2 1)"), "-1/2");
+        assert_eq!(run(&mut lisp, "(rationalp third)"), "t");
+        assert_eq!(run(&mut lisp, "(integerp third)"), "nil");
+        assert_eq!(
+            run(
+                &mut lisp,
+                "(catch-error (/ 1 0) (division-by-zero 'division-by-zero))"
+            ),
+            "division-by-zero"
+        );
     }
 }
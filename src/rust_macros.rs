@@ -25,6 +25,18 @@ macro_rules! bubble {
     }
 }
 
+// unwraps a `Result<_, Object>` whose error is an already allocated
+// `RlispError`, returning the error from the enclosing function
+#[macro_export]
+macro_rules! try_or_return {
+    ($res:expr) => {
+        match $res {
+            Ok(res) => res,
+            Err(e) => return e,
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! try_rlisp_err {
     ($l:ident : $result:expr) => {
@@ -193,8 +205,10 @@ macro_rules! get_arg {
             let mut head = $crate::types::Object::nil();
             while $consumed_args < $n_args {
                 $consumed_args += 1;
+                // bind the values of `Place`s, which may point into
+                // scopes that end before the list is done with
                 let conscell = $crate::types::ConsCell::new(
-                    pop_bubble!($l), head);
+                    pop_bubble!($l).deref_place(), head);
                 head = <$crate::lisp::Lisp as $crate::lisp::allocate::AllocObject>
                     ::alloc::<$crate::types::ConsCell>($l, conscell);
             }
@@ -0,0 +1,24 @@
+/*
+Operations on strings. `RlispString`s are UTF-8, and Lisp code indexes
+them by char rather than by byte, so the functions here translate
+between the two.
+ */
+
+pub mod string_builtins;
+
+/// the byte offset of the `n`th char of `s`. `n` may also be the
+/// number of chars in `s`, denoting its end.
+pub fn byte_offset(s: &str, n: i32) -> Option<usize> {
+    if n < 0 {
+        return None;
+    }
+    s.char_indices()
+        .map(|(i, _)| i)
+        .chain(Some(s.len()))
+        .nth(n as usize)
+}
+
+/// the index of the char starting at the byte offset `offset` of `s`
+pub fn char_index(s: &str, offset: usize) -> usize {
+    s[..offset].chars().count()
+}
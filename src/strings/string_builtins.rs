@@ -0,0 +1,218 @@
+use builtins::*;
+use types::*;
+use types::conversions::*;
+use lisp;
+use lisp::allocate::AllocObject;
+use symbols_table::SymbolLookup;
+use list::ListOps;
+use evaluator::Evaluator;
+use std::result;
+use super::{byte_offset, char_index};
+
+// the contents of the string `obj`, or a `wrong-type-error`
+fn string(l: &mut lisp::Lisp, obj: Object) -> result::Result<&'static str, Object> {
+    if let Some(s) = <&RlispString>::maybe_from(obj) {
+        Ok(s.as_ref())
+    } else {
+        let e = RlispError::wrong_type(l.type_name(RlispType::String),
+                                       l.type_name(obj.deref_place().what_type()));
+        Err(l.alloc(e))
+    }
+}
+
+// a separator for `string-split` or `string-join`, which may be a
+// string or a char, or `nil` if `obj` is
+fn separator(l: &mut lisp::Lisp, obj: Object) -> result::Result<Option<String>, Object> {
+    if obj.nilp() {
+        Ok(None)
+    } else if let Some(c) = char::maybe_from(obj) {
+        Ok(Some(c.to_string()))
+    } else {
+        string(l, obj).map(|s| Some(s.to_owned()))
+    }
+}
+
+pub fn make_builtins() -> RlispBuiltins {
+    builtin_functions!{
+        l = lisp;
+        "string-length" (string) -> {
+            let s = try_or_return!(self::string(l, string));
+            Object::from(s.chars().count() as i32)
+        },
+        "substring" (string start &optional end) -> {
+            // the chars of `string` from `start` up to `end`, or to the
+            // end of `string` if `end` isn't given
+            let s = try_or_return!(self::string(l, string));
+            let start_i = into_type_or_error!(l : start => i32);
+            let from = if let Some(from) = byte_offset(s, start_i) {
+                from
+            } else {
+                return l.alloc(RlispError::index_out_of_bounds(start.deref_place(),
+                                                               string.deref_place()));
+            };
+            let to = if end.nilp() {
+                Some(s.len())
+            } else {
+                let end_i = into_type_or_error!(l : end => i32);
+                byte_offset(s, end_i).and_then(|to| if to < from { None } else { Some(to) })
+            };
+            if let Some(to) = to {
+                l.alloc_string(&s[from..to])
+            } else {
+                l.alloc(RlispError::index_out_of_bounds(end.deref_place(), string.deref_place()))
+            }
+        },
+        "string-append" (&rest strings) -> {
+            let strings = try_rlisp_err!(l : l.arg_forms(strings));
+            let mut appended = String::new();
+            for s in strings {
+                appended.push_str(try_or_return!(self::string(l, s)));
+            }
+            l.alloc_string(&appended)
+        },
+        "string-split" (string &optional separator) -> {
+            // a list of the parts of `string` between each occurence of
+            // `separator`, or between runs of whitespace if it isn't
+            // given
+            let s = try_or_return!(self::string(l, string));
+            let parts: Vec<&str> = match try_or_return!(self::separator(l, separator)) {
+                None => s.split_whitespace().collect(),
+                Some(ref sep) if sep.is_empty() => {
+                    s.char_indices().map(|(i, c)| &s[i..i + c.len_utf8()]).collect()
+                }
+                Some(ref sep) => s.split(sep.as_str()).collect(),
+            };
+            let parts = parts.into_iter().map(|part| l.alloc_string(part)).collect();
+            l.list_from_vec(parts)
+        },
+        "string-join" (strings &optional separator) -> {
+            // the strings in the list `strings`, with `separator`
+            // between each one
+            let sep = try_or_return!(self::separator(l, separator)).unwrap_or_default();
+            let strings = try_rlisp_err!(l : l.arg_forms(strings.deref_place()));
+            let mut joined = String::new();
+            for (i, s) in strings.into_iter().enumerate() {
+                if i != 0 {
+                    joined.push_str(&sep);
+                }
+                joined.push_str(try_or_return!(self::string(l, s)));
+            }
+            l.alloc_string(&joined)
+        },
+        "string-trim" (string &optional chars) -> {
+            // `string` without any of the chars in the string `chars`,
+            // or any whitespace if it isn't given, at either end
+            let s = try_or_return!(self::string(l, string));
+            let trimmed = if chars.nilp() {
+                s.trim()
+            } else {
+                let chars = try_or_return!(self::string(l, chars));
+                s.trim_matches(|c| chars.contains(c))
+            };
+            l.alloc_string(trimmed)
+        },
+        "string-upcase" (string) -> {
+            let s = try_or_return!(self::string(l, string));
+            l.alloc_string(&s.to_uppercase())
+        },
+        "string-downcase" (string) -> {
+            let s = try_or_return!(self::string(l, string));
+            l.alloc_string(&s.to_lowercase())
+        },
+        "string-search" (needle haystack &optional start) -> {
+            // the index of the first occurence of `needle` in
+            // `haystack` at or after `start`, or `nil` if there isn't
+            // one
+            let needle = try_or_return!(self::string(l, needle));
+            let s = try_or_return!(self::string(l, haystack));
+            let start_i = if start.nilp() { 0 } else { into_type_or_error!(l : start => i32) };
+            let from = if let Some(from) = byte_offset(s, start_i) {
+                from
+            } else {
+                return l.alloc(RlispError::index_out_of_bounds(start.deref_place(),
+                                                               haystack.deref_place()));
+            };
+            s[from..]
+                .find(needle)
+                .map(|found| Object::from(char_index(s, from + found) as i32))
+                .unwrap_or_else(Object::nil)
+        },
+        "string=" (a b) -> {
+            let a = try_or_return!(self::string(l, a));
+            let b = try_or_return!(self::string(l, b));
+            Object::from(a == b)
+        },
+        "string<" (a b) -> {
+            // compares by code point, which for UTF-8 is the same as
+            // comparing by byte
+            let a = try_or_return!(self::string(l, a));
+            let b = try_or_return!(self::string(l, b));
+            Object::from(a < b)
+        },
+        "string->number" (string) -> {
+            // the number `string` would be read as, or `nil` if it
+            // isn't one
+            let s = try_or_return!(self::string(l, string));
+            ::reader::parse_number(s.as_bytes())
+                .map(|n| l.alloc_num(n))
+                .unwrap_or_else(Object::nil)
+        },
+        "number->string" (number) -> {
+            let n = into_type_or_error!(l : number => RlispNum);
+            let s = l.alloc_num(n).to_string();
+            l.alloc_string(&s)
+        },
+        "string->symbol" (string) -> {
+            let s = try_or_return!(self::string(l, string));
+            Object::from(l.make_symbol(s.as_bytes()))
+        },
+        "make-string-builder" (&optional initial) -> {
+            // a string builder whose contents start as the string
+            // `initial`, or empty
+            let initial = if initial.nilp() {
+                ""
+            } else {
+                try_or_return!(self::string(l, initial))
+            };
+            l.alloc(RlispStringBuilder::from(initial))
+        },
+        "string-builder-p" (b) -> { <&RlispStringBuilder>::is_type_or_place(b).into() },
+        "string-push" (builder c) -> {
+            // adds the char `c` to the end of `builder`, and returns
+            // `builder`
+            let b = into_type_or_error!(l : builder => &mut RlispStringBuilder);
+            let c = into_type_or_error!(l : c => char);
+            b.contents.push(c);
+            builder.deref_place()
+        },
+        "string-builder-append" (builder &rest strings) -> {
+            // adds each string (or char) in `strings` to the end of
+            // `builder`, and returns `builder`
+            let b = into_type_or_error!(l : builder => &mut RlispStringBuilder);
+            let strings = try_rlisp_err!(l : l.arg_forms(strings));
+            for s in strings {
+                if let Some(c) = char::maybe_from(s) {
+                    b.contents.push(c);
+                } else {
+                    b.contents.push_str(try_or_return!(self::string(l, s)));
+                }
+            }
+            builder.deref_place()
+        },
+        "string-builder-length" (builder) -> {
+            let b = into_type_or_error!(l : builder => &RlispStringBuilder);
+            Object::from(b.contents.chars().count() as i32)
+        },
+        "string-builder->string" (builder) -> {
+            // a new string of the contents of `builder`, which can
+            // still be added to afterwards
+            let b = into_type_or_error!(l : builder => &RlispStringBuilder);
+            l.alloc_string(&b.contents)
+        },
+        "symbol->string" (symbol) -> {
+            let sym = into_type_or_error!(l : symbol => &Symbol);
+            let name: &[u8] = sym.as_ref();
+            l.alloc_string(&String::from_utf8_lossy(name))
+        },
+    }
+}
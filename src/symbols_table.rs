@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use types::*;
 use types::rlisperror::RlispErrorKind;
 use lisp::Lisp;
-use types::conversions::FromUnchecked;
+use types::conversions::{FromUnchecked, MaybeFrom};
 use lisp::allocate::AllocObject;
 
 pub trait SymbolLookup: AllocObject {
@@ -12,6 +12,13 @@ pub trait SymbolLookup: AllocObject {
     fn new_scope(&mut self, keys_and_vals: &[(*const Symbol, Object)]) {
         let mut table = HashMap::with_capacity(keys_and_vals.len());
         for (key, val) in keys_and_vals.iter().cloned() {
+            // a `Place` may point into a namespace which will be
+            // gc'd before this one, so bind the value it points to
+            let val = if let Some(place) = Place::maybe_from(val) {
+                *place
+            } else {
+                val
+            };
             let _insert_res = table.insert(key, val);
             debug_assert!(_insert_res.is_none());
         }
@@ -23,6 +30,20 @@ pub trait SymbolLookup: AllocObject {
         self.scope_mut().pop();
         debug_assert!(!self.scope().is_empty());
     }
+    fn unwind_scope_to(&mut self, depth: usize) {
+        // end every scope pushed since the scope was `depth` deep
+        while self.scope().len() > depth {
+            self.end_scope();
+        }
+    }
+    fn block_symbol(&mut self, name: *const Symbol) -> *const Symbol {
+        // `block` binds this symbol to the id of the block named
+        // `name`. It contains a space, so the reader can't make it.
+        let name: &[u8] = unsafe { &*name }.as_ref();
+        let mut block_name = Vec::from(&b"block "[..]);
+        block_name.extend_from_slice(name);
+        self.make_symbol(&block_name)
+    }
     fn make_symbol(&mut self, sym: &[u8]) -> *const Symbol {
         let sym = Vec::from(sym);
         if self.syms_in_memory().contains_key(&sym) {
@@ -38,7 +59,9 @@ pub trait SymbolLookup: AllocObject {
         }
     }
     fn sym_ref(&mut self, sym: *const Symbol) -> Place {
-        for table in self.scope_mut() {
+        // scopes are searched innermost-first, so that local bindings
+        // shadow outer ones
+        for table in self.scope_mut().iter_mut().rev() {
             let table = unsafe { &mut **table };
             if table.contains_key(&sym) {
                 return Place::from(table.get_mut(&sym).unwrap() as *mut Object);
@@ -58,7 +81,7 @@ pub trait SymbolLookup: AllocObject {
         } else if sym_name[0] == b':' {
             Object::from(sym)
         } else {
-            for table in self.scope() {
+            for table in self.scope().iter().rev() {
                 let table = &**table;
                 if table.contains_key(&sym) {
                     return *(table.get(&sym).unwrap());
@@ -68,7 +91,7 @@ pub trait SymbolLookup: AllocObject {
         }
     }
     fn set_symbol(&mut self, sym: *const Symbol, val: Object) {
-        for table in self.scope_mut() {
+        for table in self.scope_mut().iter_mut().rev() {
             let table = unsafe { &mut **table };
             if table.contains_key(&sym) {
                 if let Some(sym_val) = table.get_mut(&sym) {
@@ -93,9 +116,16 @@ pub trait SymbolLookup: AllocObject {
             b"boolean" => Some(RlispType::Bool),
             b"error" => Some(RlispType::Error),
             b"integer" => Some(RlispType::Integer),
+            b"rational" => Some(RlispType::Rational),
             b"namespace" => Some(RlispType::Namespace),
             b"float" => Some(RlispType::Float),
+            b"character" => Some(RlispType::Char),
             b"place" => Some(RlispType::Place),
+            b"vector" => Some(RlispType::Vector),
+            b"hash-table" => Some(RlispType::HashTable),
+            b"string-builder" => Some(RlispType::StringBuilder),
+            b"bignum" => Some(RlispType::Bignum),
+            b"ratio" => Some(RlispType::Ratio),
             _ => None,
         }
     }
@@ -109,9 +139,16 @@ pub trait SymbolLookup: AllocObject {
             RlispType::Bool => b"boolean",
             RlispType::Error => b"error",
             RlispType::Integer => b"integer",
+            RlispType::Rational => b"rational",
             RlispType::Namespace => b"namespace",
             RlispType::Float => b"float",
+            RlispType::Char => b"character",
             RlispType::Place => b"place",
+            RlispType::Vector => b"vector",
+            RlispType::HashTable => b"hash-table",
+            RlispType::StringBuilder => b"string-builder",
+            RlispType::Bignum => b"bignum",
+            RlispType::Ratio => b"ratio",
         }))
     }
     fn error_name(&mut self, err: &RlispErrorKind) -> Object {
@@ -124,6 +161,14 @@ pub trait SymbolLookup: AllocObject {
             RlispErrorKind::NotAType { .. } => b"type-designator-error",
             RlispErrorKind::UndefinedSymbol { .. } => b"undefined-symbol-error",
             RlispErrorKind::IndexOutOfBounds { .. } => b"index-out-of-bounds-error",
+            RlispErrorKind::StackDepthExceeded { .. } => b"stack-depth-exceeded-error",
+            RlispErrorKind::ControlError { .. } => b"control-error",
+            RlispErrorKind::UnknownKeyword { .. } => b"unknown-keyword-error",
+            RlispErrorKind::MissingKeywordValue { .. } => b"missing-keyword-value-error",
+            RlispErrorKind::DestructuringMismatch { .. } => b"destructuring-error",
+            RlispErrorKind::RestartInvoked { .. } => b"restart-invoked",
+            RlispErrorKind::ReturnFrom { .. } => b"return-from",
+            RlispErrorKind::Throw { .. } => b"throw",
             RlispErrorKind::Custom { kind, .. } => {
                 return kind;
             }
@@ -0,0 +1,63 @@
+/*
+A heap-allocated integer too large for an immediate. Arithmetic is
+done on `RlispNum`s, which are only boxed up as `RlispBignum`s (by
+`AllocObject::alloc_num`) when they don't fit in an `i32`, so an
+`RlispBignum` never holds a value which would fit in one.
+*/
+
+use std::fmt;
+use gc::{GarbageCollected, GcMark};
+use math::bigint::BigInt;
+use types::*;
+
+pub struct RlispBignum {
+    pub gc_marking: GcMark,
+    pub value: BigInt,
+}
+
+impl convert::From<BigInt> for RlispBignum {
+    fn from(value: BigInt) -> Self {
+        debug_assert!(value.to_i32().is_none());
+        Self {
+            gc_marking: 0,
+            value,
+        }
+    }
+}
+
+impl GarbageCollected for RlispBignum {
+    fn my_marking(&self) -> &GcMark {
+        &self.gc_marking
+    }
+    fn my_marking_mut(&mut self) -> &mut GcMark {
+        &mut self.gc_marking
+    }
+    fn gc_mark_children(&mut self, _mark: GcMark) {
+        // `RlispBignum`s don't have any children, so this is a no-op
+    }
+}
+
+impl fmt::Display for RlispBignum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl fmt::Debug for RlispBignum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl FromUnchecked<Object> for *mut RlispBignum {
+    unsafe fn from_unchecked(obj: Object) -> *mut RlispBignum {
+        debug_assert!(obj.bignump());
+        ObjectTag::Bignum.untag(obj.0) as *mut RlispBignum
+    }
+}
+
+impl FromObject for *mut RlispBignum {
+    fn rlisp_type() -> RlispType {
+        RlispType::Bignum
+    }
+}
@@ -154,6 +154,19 @@ impl FromObject for i32 {
     }
 }
 
+impl FromUnchecked<Object> for char {
+    unsafe fn from_unchecked(obj: Object) -> char {
+        debug_assert!(obj.characterp());
+        ::std::char::from_u32_unchecked(ImmediateTag::Char.untag(obj.0) as u32)
+    }
+}
+
+impl FromObject for char {
+    fn rlisp_type() -> RlispType {
+        RlispType::Char
+    }
+}
+
 impl FromUnchecked<Object> for bool {
     unsafe fn from_unchecked(obj: Object) -> bool {
         debug_assert!(obj.boolp());
@@ -188,6 +201,14 @@ mod test {
         assert!(<&Namespace>::maybe_from(many).is_none());
     }
     #[test]
+    fn characters() {
+        let lambda = Object::from('λ');
+        assert_eq!(char::maybe_from(lambda), Some('λ'));
+        assert!(i32::maybe_from(lambda).is_none());
+        assert!(!lambda.boolp());
+        assert!(!Object::from(1).boolp());
+    }
+    #[test]
     fn pointers() {
         let a_pointer = 0xdead_beef as *const ConsCell;
         let obj = Object::from(a_pointer);
@@ -13,6 +13,28 @@ pub enum ArgType {
     Mandatory,
     Optional,
     Rest,
+    Key,
+}
+
+// one parameter from an arglist. `&optional` and `&key` parameters
+// may be written `(NAME DEFAULT SUPPLIED-P)`, where `DEFAULT` is a
+// form evaluated if no arg is passed and `SUPPLIED-P` is bound to
+// whether one was.
+#[derive(Clone)]
+pub struct Param {
+    pub var: ParamVar,
+    pub arg_type: ArgType,
+    pub default: Option<Object>,
+    pub supplied_p: Option<*const Symbol>,
+}
+
+#[derive(Clone)]
+pub enum ParamVar {
+    Symbol(*const Symbol),
+    // a mandatory parameter written as a nested arglist, which
+    // destructures its arg. The `Object` is the nested arglist as
+    // written.
+    Pattern(Object, Vec<Param>),
 }
 
 pub struct RlispFunc {
@@ -25,6 +47,9 @@ pub struct RlispFunc {
     pub scope: Option<Vec<*mut Namespace>>,
     gc_marking: GcMark,
     name: Option<Object>,
+    // macros are `LispFn`s which are called on their unevaluated
+    // args and whose return value is then evaluated in their place
+    is_macro: bool,
 }
 
 pub enum FunctionBody {
@@ -41,6 +66,7 @@ impl RlispFunc {
             gc_marking: 0,
             name: None,
             scope: None,
+            is_macro: false,
         }
     }
     pub fn from_builtin(fun: Box<builtins::RlispBuiltinFunc>) -> Self {
@@ -50,6 +76,7 @@ impl RlispFunc {
             gc_marking: 0,
             name: None,
             scope: None,
+            is_macro: false,
         }
     }
     pub fn from_special_form(fun: Box<builtins::RlispSpecialForm>) -> Self {
@@ -59,6 +86,7 @@ impl RlispFunc {
             gc_marking: 0,
             name: None,
             scope: None,
+            is_macro: false,
         }
     }
     pub fn with_arglist(mut self, arglist: Object) -> Self {
@@ -73,6 +101,16 @@ impl RlispFunc {
         self.scope = Some(scope);
         self
     }
+    pub fn as_macro(mut self) -> Self {
+        self.is_macro = true;
+        self
+    }
+    pub fn is_macro(&self) -> bool {
+        self.is_macro
+    }
+    pub fn name(&self) -> Option<Object> {
+        self.name
+    }
 }
 
 impl GarbageCollected for RlispFunc {
@@ -111,7 +149,8 @@ impl fmt::Debug for RlispFunc {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "[ function {} ({}) -> {:?} ]",
+            "[ {} {} ({}) -> {:?} ]",
+            if self.is_macro { "macro" } else { "function" },
             self.name.unwrap_or_else(Object::nil),
             self.arglist.unwrap_or_else(Object::nil),
             self.body
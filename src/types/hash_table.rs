@@ -0,0 +1,162 @@
+/*
+A hash table keyed by arbitrary Objects, compared either with `eq`
+(bitwise equality) or with `equal` (see `Object::equal`).
+*/
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::fmt;
+use gc::{GarbageCollected, GcMark};
+use types::*;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HashTest {
+    Eq,
+    Equal,
+}
+
+// A key along with the test of the table it belongs to, so that
+// `HashMap` hashes and compares it the way that table wants.
+#[derive(Copy, Clone)]
+struct Key {
+    obj: Object,
+    test: HashTest,
+}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.test {
+            HashTest::Eq => self.obj.0.hash(state),
+            HashTest::Equal => hash_equal(self.obj, state),
+        }
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Key) -> bool {
+        match self.test {
+            HashTest::Eq => self.obj == other.obj,
+            HashTest::Equal => self.obj.equal(other.obj),
+        }
+    }
+}
+
+impl Eq for Key {}
+
+// hashes `obj` so that objects which are `equal` hash the same
+fn hash_equal<H: Hasher>(obj: Object, state: &mut H) {
+    let mut obj = obj.deref_place();
+    while let Some(cons) = <&ConsCell>::maybe_from(obj) {
+        hash_equal(cons.car, state);
+        obj = cons.cdr.deref_place();
+    }
+    if let Some(string) = <&RlispString>::maybe_from(obj) {
+        let bytes: &[u8] = string.as_ref();
+        bytes.hash(state);
+    } else if let Some(bignum) = <&RlispBignum>::maybe_from(obj) {
+        bignum.value.to_string().hash(state);
+    } else if let Some(ratio) = <&RlispRatio>::maybe_from(obj) {
+        ratio.to_string().hash(state);
+    } else {
+        obj.0.hash(state);
+    }
+}
+
+pub struct RlispHashTable {
+    pub gc_marking: GcMark,
+    test: HashTest,
+    table: HashMap<Key, Object>,
+}
+
+impl RlispHashTable {
+    pub fn new(test: HashTest) -> Self {
+        Self {
+            gc_marking: 0,
+            test,
+            table: HashMap::new(),
+        }
+    }
+    pub fn test(&self) -> HashTest {
+        self.test
+    }
+    fn key(&self, obj: Object) -> Key {
+        Key {
+            obj: obj.deref_place(),
+            test: self.test,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+    /// the value stored under `key`, if there is one
+    pub fn get_ref(&self, key: Object) -> Option<&Object> {
+        self.table.get(&self.key(key))
+    }
+    /// the value stored under `key`, which is first added to the
+    /// table with the value `default` if it isn't there
+    pub fn entry(&mut self, key: Object, default: Object) -> &mut Object {
+        let key = self.key(key);
+        self.table.entry(key).or_insert(default)
+    }
+    /// removes `key` from the table, returning true if it was there
+    pub fn remove(&mut self, key: Object) -> bool {
+        let key = self.key(key);
+        self.table.remove(&key).is_some()
+    }
+    /// a snapshot of the keys and values in the table
+    pub fn entries(&self) -> Vec<(Object, Object)> {
+        self.table.iter().map(|(key, val)| (key.obj, *val)).collect()
+    }
+}
+
+impl GarbageCollected for RlispHashTable {
+    fn my_marking(&self) -> &GcMark {
+        &self.gc_marking
+    }
+    fn my_marking_mut(&mut self) -> &mut GcMark {
+        &mut self.gc_marking
+    }
+    fn gc_mark_children(&mut self, mark: GcMark) {
+        for (key, val) in &self.table {
+            key.obj.gc_mark(mark);
+            val.gc_mark(mark);
+        }
+    }
+}
+
+impl fmt::Display for RlispHashTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let test = match self.test {
+            HashTest::Eq => "eq",
+            HashTest::Equal => "equal",
+        };
+        write!(f, "<hash-table :test {}>", test)
+    }
+}
+
+impl fmt::Debug for RlispHashTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln!(f, "{} {{", self)?;
+        for (key, val) in &self.table {
+            writeln!(f, "({:?} . {:?})", key.obj, val)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl FromUnchecked<Object> for *mut RlispHashTable {
+    unsafe fn from_unchecked(obj: Object) -> *mut RlispHashTable {
+        debug_assert!(obj.hash_tablep());
+        ObjectTag::HashTable.untag(obj.0) as *mut RlispHashTable
+    }
+}
+
+impl FromObject for *mut RlispHashTable {
+    fn rlisp_type() -> RlispType {
+        RlispType::HashTable
+    }
+}
@@ -48,7 +48,22 @@ pub mod num;
 pub use self::num::RlispNum;
 
 pub mod places;
-pub use self::places::Place;
+pub use self::places::{ElementPlace, Place};
+
+pub mod vector;
+pub use self::vector::RlispVector;
+
+pub mod hash_table;
+pub use self::hash_table::{HashTest, RlispHashTable};
+
+pub mod string_builder;
+pub use self::string_builder::RlispStringBuilder;
+
+pub mod bignum;
+pub use self::bignum::RlispBignum;
+
+pub mod ratio;
+pub use self::ratio::RlispRatio;
 
 ///  Any NaN has these bits set
 const NAN_MASK: u64 = 0b111_1111_1111 << 52;
@@ -61,6 +76,10 @@ const _MAX_IMMEDIATE: u64 = 1 << 32;
 /// for type-checking Objects
 const OBJECT_TAG_MASK: u64 = 0b1111 << 48;
 
+/// for type-checking immediates, whose `ImmediateTag` is stored in
+/// the 16 bits between their value and their `ObjectTag`
+const IMMEDIATE_TAG_MASK: u64 = 0xffff << 32;
+
 /// A NaN-boxed Rlisp object, containing either an `f64` or a variant of
 /// `ObjectTag`
 #[derive(Copy, Clone)]
@@ -99,6 +118,24 @@ enum ObjectTag {
     Immediate,
 
     Place,
+
+    /// *const RlispVector / *mut RlispVector
+    Vector,
+
+    /// *const RlispHashTable / *mut RlispHashTable
+    HashTable,
+
+    /// *const RlispStringBuilder / *mut RlispStringBuilder
+    StringBuilder,
+
+    /// *const RlispBignum / *mut RlispBignum
+    Bignum,
+
+    /// *const RlispRatio / *mut RlispRatio
+    Ratio,
+
+    /// *mut ElementPlace; a `Place` like those tagged `Place`
+    ElementPlace,
 }
 
 impl convert::From<ObjectTag> for u64 {
@@ -149,6 +186,8 @@ impl ObjectTag {
 pub enum ImmediateTag {
     Bool,
     Integer,
+    /// a Unicode scalar value
+    Char,
 }
 
 impl convert::From<ImmediateTag> for u64 {
@@ -163,7 +202,7 @@ impl ImmediateTag {
         ObjectTag::Immediate.tag(u64::from(self) ^ val)
     }
     fn is_of_type(self, val: u64) -> bool {
-        ObjectTag::Immediate.is_of_type(val) && (val & (u64::from(self))) == (u64::from(self))
+        ObjectTag::Immediate.is_of_type(val) && (val & IMMEDIATE_TAG_MASK) == u64::from(self)
     }
     fn untag(self, val: u64) -> u64 {
         debug_assert!(self.is_of_type(val));
@@ -178,7 +217,9 @@ pub enum RlispType {
     Cons,
     Number,
     Integer,
+    Rational,
     Float,
+    Char,
     Sym,
     String,
     Function,
@@ -186,6 +227,11 @@ pub enum RlispType {
     Error,
     Namespace,
     Place,
+    Vector,
+    HashTable,
+    StringBuilder,
+    Bignum,
+    Ratio,
 }
 
 impl RlispType {
@@ -193,14 +239,25 @@ impl RlispType {
         match self {
             RlispType::Cons => <*const ConsCell>::is_type_or_place(obj),
             RlispType::Number => RlispNum::is_type_or_place(obj),
-            RlispType::Integer => i32::is_type_or_place(obj),
+            RlispType::Integer => {
+                i32::is_type_or_place(obj) || <*const RlispBignum>::is_type_or_place(obj)
+            }
+            RlispType::Rational => {
+                RlispType::Integer.check_type(obj) || <*const RlispRatio>::is_type_or_place(obj)
+            }
             RlispType::Float => f64::is_type_or_place(obj),
+            RlispType::Char => char::is_type_or_place(obj),
             RlispType::Sym => <*const Symbol>::is_type_or_place(obj),
             RlispType::String => <*const RlispString>::is_type_or_place(obj),
             RlispType::Function => <*const RlispFunc>::is_type_or_place(obj),
             RlispType::Bool => bool::is_type_or_place(obj),
             RlispType::Error => <*const RlispError>::is_type_or_place(obj),
             RlispType::Namespace => <*const Namespace>::is_type_or_place(obj),
+            RlispType::Vector => <*const RlispVector>::is_type_or_place(obj),
+            RlispType::HashTable => <*const RlispHashTable>::is_type_or_place(obj),
+            RlispType::StringBuilder => <*const RlispStringBuilder>::is_type_or_place(obj),
+            RlispType::Bignum => <*const RlispBignum>::is_type_or_place(obj),
+            RlispType::Ratio => <*const RlispRatio>::is_type_or_place(obj),
             RlispType::Place => {
                 let place = unsafe { Place::from_unchecked(obj) };
                 self.check_type(*place)
@@ -248,6 +305,9 @@ impl Object {
     pub fn integerp(self) -> bool {
         ImmediateTag::Integer.is_of_type(self.0)
     }
+    pub fn characterp(self) -> bool {
+        ImmediateTag::Char.is_of_type(self.0)
+    }
     pub fn symbolp(self) -> bool {
         ObjectTag::Sym.is_of_type(self.0)
     }
@@ -279,8 +339,71 @@ impl Object {
         ObjectTag::Namespace.is_of_type(self.0)
     }
 
+    pub fn vectorp(self) -> bool {
+        ObjectTag::Vector.is_of_type(self.0)
+    }
+
+    pub fn hash_tablep(self) -> bool {
+        ObjectTag::HashTable.is_of_type(self.0)
+    }
+
+    pub fn string_builderp(self) -> bool {
+        ObjectTag::StringBuilder.is_of_type(self.0)
+    }
+
+    pub fn bignump(self) -> bool {
+        ObjectTag::Bignum.is_of_type(self.0)
+    }
+
+    pub fn ratiop(self) -> bool {
+        ObjectTag::Ratio.is_of_type(self.0)
+    }
+
     pub fn placep(self) -> bool {
-        ObjectTag::Place.is_of_type(self.0)
+        ObjectTag::Place.is_of_type(self.0) || ObjectTag::ElementPlace.is_of_type(self.0)
+    }
+
+    /// if self is a place, returns the object it refers to (following
+    /// places to places as well); otherwise returns self.
+    pub fn deref_place(self) -> Object {
+        let mut obj = self;
+        while obj.placep() {
+            obj = *unsafe { Place::from_unchecked(obj) };
+        }
+        obj
+    }
+
+    /// structural equality, as in Common Lisp's `equal`: conses are
+    /// `equal` if their cars and cdrs are, strings if they have the
+    /// same contents, bignums and ratios if they have the same value,
+    /// and anything else only if it is `eq`.
+    pub fn equal(self, other: Object) -> bool {
+        let (mut a, mut b) = (self.deref_place(), other.deref_place());
+        loop {
+            if a == b {
+                return true;
+            }
+            match (<&ConsCell>::maybe_from(a), <&ConsCell>::maybe_from(b)) {
+                (Some(a_cons), Some(b_cons)) => {
+                    if !a_cons.car.equal(b_cons.car) {
+                        return false;
+                    }
+                    a = a_cons.cdr.deref_place();
+                    b = b_cons.cdr.deref_place();
+                }
+                _ => break,
+            }
+        }
+        if let (Some(a), Some(b)) = (<&RlispString>::maybe_from(a), <&RlispString>::maybe_from(b)) {
+            let (a, b): (&[u8], &[u8]) = (a.as_ref(), b.as_ref());
+            a == b
+        } else if let (Some(a), Some(b)) = (<&RlispBignum>::maybe_from(a), <&RlispBignum>::maybe_from(b)) {
+            a.value == b.value
+        } else if let (Some(a), Some(b)) = (<&RlispRatio>::maybe_from(a), <&RlispRatio>::maybe_from(b)) {
+            a.numerator == b.numerator && a.denominator == b.denominator
+        } else {
+            false
+        }
     }
 
     /// the logical inverse of casting an Object to bool; true iff
@@ -302,6 +425,8 @@ impl Object {
             RlispType::Float
         } else if self.integerp() {
             RlispType::Integer
+        } else if self.characterp() {
+            RlispType::Char
         } else if self.consp() {
             RlispType::Cons
         } else if self.symbolp() {
@@ -318,6 +443,16 @@ impl Object {
             RlispType::Namespace
         } else if self.placep() {
             RlispType::Place
+        } else if self.vectorp() {
+            RlispType::Vector
+        } else if self.hash_tablep() {
+            RlispType::HashTable
+        } else if self.string_builderp() {
+            RlispType::StringBuilder
+        } else if self.bignump() {
+            RlispType::Bignum
+        } else if self.ratiop() {
+            RlispType::Ratio
         } else {
             unreachable!()
         }
@@ -331,7 +466,12 @@ impl Object {
     pub fn gc_mark(self, marking: ::gc::GcMark) {
         unsafe {
             match self.what_type() {
-                RlispType::Number | RlispType::Float | RlispType::Integer | RlispType::Bool => (),
+                RlispType::Number
+                | RlispType::Float
+                | RlispType::Integer
+                | RlispType::Rational
+                | RlispType::Char
+                | RlispType::Bool => (),
                 RlispType::Cons => {
                     <&mut ConsCell>::from_unchecked(self).gc_mark(marking);
                 }
@@ -350,7 +490,25 @@ impl Object {
                 RlispType::Namespace => {
                     <&mut Namespace>::from_unchecked(self).gc_mark(marking);
                 }
-                RlispType::Place => (*(Place::from_unchecked(self))).gc_mark(marking),
+                RlispType::Vector => {
+                    <&mut RlispVector>::from_unchecked(self).gc_mark(marking);
+                }
+                RlispType::HashTable => {
+                    <&mut RlispHashTable>::from_unchecked(self).gc_mark(marking);
+                }
+                RlispType::StringBuilder => {
+                    <&mut RlispStringBuilder>::from_unchecked(self).gc_mark(marking);
+                }
+                RlispType::Bignum => {
+                    <&mut RlispBignum>::from_unchecked(self).gc_mark(marking);
+                }
+                RlispType::Ratio => {
+                    <&mut RlispRatio>::from_unchecked(self).gc_mark(marking);
+                }
+                RlispType::Place => match Place::from_unchecked(self) {
+                    Place::Slot(slot) => (*slot).gc_mark(marking),
+                    Place::Element(element) => (*element).gc_mark(marking),
+                },
             }
         }
     }
@@ -363,9 +521,12 @@ impl Object {
     pub fn should_dealloc(self, marking: ::gc::GcMark) -> bool {
         unsafe {
             match self.what_type() {
-                RlispType::Number | RlispType::Float | RlispType::Integer | RlispType::Bool => {
-                    false
-                }
+                RlispType::Number
+                | RlispType::Float
+                | RlispType::Integer
+                | RlispType::Rational
+                | RlispType::Char
+                | RlispType::Bool => false,
                 RlispType::Cons => <&mut ConsCell>::from_unchecked(self).should_dealloc(marking),
                 RlispType::Sym => <&mut Symbol>::from_unchecked(self).should_dealloc(marking),
                 RlispType::String => {
@@ -378,7 +539,21 @@ impl Object {
                 RlispType::Namespace => {
                     <&mut Namespace>::from_unchecked(self).should_dealloc(marking)
                 }
-                RlispType::Place => (*(Place::from_unchecked(self))).should_dealloc(marking),
+                RlispType::Vector => {
+                    <&mut RlispVector>::from_unchecked(self).should_dealloc(marking)
+                }
+                RlispType::HashTable => {
+                    <&mut RlispHashTable>::from_unchecked(self).should_dealloc(marking)
+                }
+                RlispType::StringBuilder => {
+                    <&mut RlispStringBuilder>::from_unchecked(self).should_dealloc(marking)
+                }
+                RlispType::Bignum => <&mut RlispBignum>::from_unchecked(self).should_dealloc(marking),
+                RlispType::Ratio => <&mut RlispRatio>::from_unchecked(self).should_dealloc(marking),
+                RlispType::Place => match Place::from_unchecked(self) {
+                    Place::Slot(slot) => (*slot).should_dealloc(marking),
+                    Place::Element(element) => (*element).should_dealloc(marking),
+                },
             }
         }
     }
@@ -388,9 +563,10 @@ impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         unsafe {
             match self.what_type() {
-                RlispType::Number => unreachable!(),
+                RlispType::Number | RlispType::Rational => unreachable!(),
                 RlispType::Float => write!(f, "{}", f64::from_unchecked(*self)),
                 RlispType::Integer => write!(f, "{}", i32::from_unchecked(*self)),
+                RlispType::Char => write_char(f, char::from_unchecked(*self)),
                 RlispType::Bool => {
                     if self.nilp() {
                         write!(f, "nil")
@@ -404,6 +580,15 @@ impl fmt::Display for Object {
                 RlispType::Function => write!(f, "{}", <&RlispFunc>::from_unchecked(*self)),
                 RlispType::Error => write!(f, "{}", <&RlispError>::from_unchecked(*self)),
                 RlispType::Namespace => write!(f, "{}", <&Namespace>::from_unchecked(*self)),
+                RlispType::Vector => write!(f, "{}", <&RlispVector>::from_unchecked(*self)),
+                RlispType::HashTable => {
+                    write!(f, "{}", <&RlispHashTable>::from_unchecked(*self))
+                }
+                RlispType::StringBuilder => {
+                    write!(f, "{}", <&RlispStringBuilder>::from_unchecked(*self))
+                }
+                RlispType::Bignum => write!(f, "{}", <&RlispBignum>::from_unchecked(*self)),
+                RlispType::Ratio => write!(f, "{}", <&RlispRatio>::from_unchecked(*self)),
                 RlispType::Place => write!(f, "{}", Place::from_unchecked(*self)),
             }
         }
@@ -414,9 +599,10 @@ impl fmt::Debug for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         unsafe {
             match self.what_type() {
-                RlispType::Number => unreachable!(),
+                RlispType::Number | RlispType::Rational => unreachable!(),
                 RlispType::Float => write!(f, "{}", f64::from_unchecked(*self)),
                 RlispType::Integer => write!(f, "{}", i32::from_unchecked(*self)),
+                RlispType::Char => write_char(f, char::from_unchecked(*self)),
                 RlispType::Bool => {
                     if self.nilp() {
                         write!(f, "nil")
@@ -430,12 +616,31 @@ impl fmt::Debug for Object {
                 RlispType::Function => write!(f, "{:?}", <&RlispFunc>::from_unchecked(*self)),
                 RlispType::Error => write!(f, "{}", <&RlispError>::from_unchecked(*self)),
                 RlispType::Namespace => write!(f, "{:?}", <&Namespace>::from_unchecked(*self)),
+                RlispType::Vector => write!(f, "{:?}", <&RlispVector>::from_unchecked(*self)),
+                RlispType::HashTable => {
+                    write!(f, "{:?}", <&RlispHashTable>::from_unchecked(*self))
+                }
+                RlispType::StringBuilder => {
+                    write!(f, "{:?}", <&RlispStringBuilder>::from_unchecked(*self))
+                }
+                RlispType::Bignum => write!(f, "{:?}", <&RlispBignum>::from_unchecked(*self)),
+                RlispType::Ratio => write!(f, "{:?}", <&RlispRatio>::from_unchecked(*self)),
                 RlispType::Place => write!(f, "{:?}", Place::from_unchecked(*self)),
             }
         }
     }
 }
 
+/// writes `c` the way the reader reads it, as `#\c` or `#\NAME`
+fn write_char(f: &mut fmt::Formatter, c: char) -> fmt::Result {
+    match c {
+        ' ' => write!(f, "#\\space"),
+        '\n' => write!(f, "#\\newline"),
+        '\t' => write!(f, "#\\tab"),
+        _ => write!(f, "#\\{}", c),
+    }
+}
+
 impl Default for Object {
     /// the default Object is `t`
     fn default() -> Self {
@@ -450,16 +655,6 @@ impl convert::From<Object> for bool {
     }
 }
 
-impl convert::From<RlispNum> for Object {
-    fn from(n: RlispNum) -> Self {
-        if let Some(i) = i32::maybe_from(n) {
-            Object::from(i)
-        } else {
-            Object::from(f64::from(n))
-        }
-    }
-}
-
 impl convert::From<*const RlispString> for Object {
     fn from(ptr: *const RlispString) -> Self {
         let ptr = ptr as u64;
@@ -502,6 +697,41 @@ impl convert::From<*const Namespace> for Object {
     }
 }
 
+impl convert::From<*const RlispVector> for Object {
+    fn from(ptr: *const RlispVector) -> Self {
+        let ptr = ptr as u64;
+        Object(ObjectTag::Vector.tag(ptr))
+    }
+}
+
+impl convert::From<*const RlispHashTable> for Object {
+    fn from(ptr: *const RlispHashTable) -> Self {
+        let ptr = ptr as u64;
+        Object(ObjectTag::HashTable.tag(ptr))
+    }
+}
+
+impl convert::From<*const RlispStringBuilder> for Object {
+    fn from(ptr: *const RlispStringBuilder) -> Self {
+        let ptr = ptr as u64;
+        Object(ObjectTag::StringBuilder.tag(ptr))
+    }
+}
+
+impl convert::From<*const RlispBignum> for Object {
+    fn from(ptr: *const RlispBignum) -> Self {
+        let ptr = ptr as u64;
+        Object(ObjectTag::Bignum.tag(ptr))
+    }
+}
+
+impl convert::From<*const RlispRatio> for Object {
+    fn from(ptr: *const RlispRatio) -> Self {
+        let ptr = ptr as u64;
+        Object(ObjectTag::Ratio.tag(ptr))
+    }
+}
+
 impl<T> convert::From<*mut T> for Object
 where
     Object: convert::From<*const T>,
@@ -527,6 +757,12 @@ impl convert::From<f64> for Object {
     }
 }
 
+impl convert::From<char> for Object {
+    fn from(c: char) -> Self {
+        Object(ImmediateTag::Char.tag(u64::from(c as u32)))
+    }
+}
+
 impl convert::From<i32> for Object {
     fn from(num: i32) -> Self {
         Object(ImmediateTag::Integer.tag(u64::from(num as u32)))
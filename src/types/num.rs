@@ -1,11 +1,20 @@
 use types::*;
 use math;
-use std::{cmp, convert, ops};
+use math::bigint::BigInt;
+use std::{cmp, convert, mem, ops};
 
-#[derive(Clone, Copy)]
+/// A number, for doing arithmetic on. Integer arithmetic which
+/// overflows an `i32` is promoted to a `BigInt`, and a `BigInt` result
+/// which fits in an `i32` is demoted again, so a `RlispNum::Big` never
+/// holds a value which would fit in a `RlispNum::Int`. Likewise, a
+/// `RlispNum::Ratio` is a numerator and denominator in lowest terms,
+/// whose denominator is always greater than 1.
+#[derive(Clone)]
 pub enum RlispNum {
     Int(i32),
     Float(f64),
+    Big(BigInt),
+    Ratio(BigInt, BigInt),
 }
 
 fn fits_in_an_int(f: f64) -> bool {
@@ -14,16 +23,51 @@ fn fits_in_an_int(f: f64) -> bool {
 fn try_flatten_float(f: f64) -> RlispNum {
     if math::integerp(f) && fits_in_an_int(f) {
         RlispNum::Int(f as i32)
+    } else if math::integerp(f) && f.is_finite() {
+        // too big for an `Int`, but still a whole number
+        BigInt::from_f64(f).map(demote).unwrap_or(RlispNum::Float(f))
     } else {
         RlispNum::Float(f)
     }
 }
 
+// `big` as an `RlispNum`, demoted to an `Int` if it fits in one
+fn demote(big: BigInt) -> RlispNum {
+    if let Some(i) = big.to_i32() {
+        RlispNum::Int(i)
+    } else {
+        RlispNum::Big(big)
+    }
+}
+
 impl RlispNum {
+    /// `numerator / denominator` in lowest terms, which is an integer
+    /// if it divides evenly, or `None` if `denominator` is zero
+    pub fn ratio(numerator: &BigInt, denominator: &BigInt) -> Option<Self> {
+        if denominator.is_zero() {
+            return None;
+        }
+        let gcd = numerator.gcd(denominator);
+        let (mut numerator, _) = numerator.divrem(&gcd)?;
+        let (mut denominator, _) = denominator.divrem(&gcd)?;
+        if denominator.is_negative() {
+            numerator = -&numerator;
+            denominator = -&denominator;
+        }
+        if denominator == BigInt::from(1) {
+            Some(demote(numerator))
+        } else {
+            Some(RlispNum::Ratio(numerator, denominator))
+        }
+    }
     pub fn abs(self) -> Self {
         match self {
             RlispNum::Float(f) => Self::from(f.abs()),
-            RlispNum::Int(i) => Self::from(i.abs()),
+            RlispNum::Int(i) => i.checked_abs()
+                .map(RlispNum::Int)
+                .unwrap_or_else(|| demote(BigInt::from(i).abs())),
+            RlispNum::Big(b) => demote(b.abs()),
+            RlispNum::Ratio(n, d) => RlispNum::Ratio(n.abs(), d),
         }
     }
     pub fn try_flatten(self) -> Self {
@@ -33,38 +77,145 @@ impl RlispNum {
             self
         }
     }
-    pub fn trunc(self) -> Self {
-        if let RlispNum::Float(f) = self {
-            let i = f.trunc();
-            try_flatten_float(i)
-        } else {
-            self
+    // rounds a `Float` with `float`, or a `Ratio` by passing the
+    // quotient and remainder of its numerator and denominator to
+    // `ratio`, which returns the rounded integer
+    fn round_with<F, R>(self, float: F, ratio: R) -> Self
+    where
+        F: FnOnce(f64) -> f64,
+        R: FnOnce(BigInt, &BigInt, &BigInt) -> BigInt,
+    {
+        match self {
+            RlispNum::Float(f) => try_flatten_float(float(f)),
+            RlispNum::Ratio(n, d) => {
+                let (quotient, remainder) = n.divrem(&d).unwrap();
+                demote(ratio(quotient, &remainder, &d))
+            }
+            _ => self,
         }
     }
+    pub fn trunc(self) -> Self {
+        self.round_with(f64::trunc, |quotient, _, _| quotient)
+    }
     pub fn floor(self) -> Self {
-        if let RlispNum::Float(f) = self {
-            let i = f.floor();
-            try_flatten_float(i)
-        } else {
-            self
-        }
+        self.round_with(f64::floor, |quotient, remainder, _| {
+            if remainder.is_negative() {
+                &quotient - &BigInt::from(1)
+            } else {
+                quotient
+            }
+        })
     }
     pub fn ceil(self) -> Self {
-        if let RlispNum::Float(f) = self {
-            let i = f.ceil();
-            try_flatten_float(i)
-        } else {
-            self
-        }
+        self.round_with(f64::ceil, |quotient, remainder, _| {
+            if remainder.is_negative() || remainder.is_zero() {
+                quotient
+            } else {
+                &quotient + &BigInt::from(1)
+            }
+        })
     }
     pub fn round(self) -> Self {
+        // halfway cases round away from zero, like `f64::round`
+        self.round_with(f64::round, |quotient, remainder, denominator| {
+            if remainder.abs().shl(1) < *denominator {
+                quotient
+            } else if remainder.is_negative() {
+                &quotient - &BigInt::from(1)
+            } else {
+                &quotient + &BigInt::from(1)
+            }
+        })
+    }
+    /// the exact value of `self` as a rational, or `None` if it is an
+    /// infinite or NaN float
+    pub fn rational(self) -> Option<Self> {
         if let RlispNum::Float(f) = self {
-            let i = f.round();
-            try_flatten_float(i)
+            if !f.is_finite() {
+                return None;
+            }
+            if math::integerp(f) {
+                return Some(try_flatten_float(f));
+            }
+            // `f` is a 53 bit mantissa times 2 to the power of a
+            // negative exponent
+            let bits = f.to_bits();
+            let exponent = ((bits >> 52) & 0x7ff) as usize;
+            let (mantissa, shift) = if exponent == 0 {
+                (bits & ((1 << 52) - 1), 1074)
+            } else {
+                ((bits & ((1 << 52) - 1)) | (1 << 52), 1075 - exponent)
+            };
+            let numerator = BigInt::from(mantissa as i64);
+            let numerator = if f < 0.0 { -&numerator } else { numerator };
+            RlispNum::ratio(&numerator, &BigInt::from(1).shl(shift))
         } else {
-            self
+            Some(self)
+        }
+    }
+    /// true for integers and ratios, and false for floats
+    pub fn is_rational(&self) -> bool {
+        match *self {
+            RlispNum::Float(_) => false,
+            _ => true,
+        }
+    }
+    pub fn to_f64(&self) -> f64 {
+        match *self {
+            RlispNum::Int(i) => f64::from(i),
+            RlispNum::Float(f) => f,
+            RlispNum::Big(ref b) => b.to_f64(),
+            RlispNum::Ratio(ref n, ref d) => n.to_f64() / d.to_f64(),
+        }
+    }
+    // `self` as a `BigInt`, or `None` if it isn't an integer
+    fn to_big(&self) -> Option<BigInt> {
+        match *self {
+            RlispNum::Int(i) => Some(BigInt::from(i)),
+            RlispNum::Big(ref b) => Some(b.clone()),
+            RlispNum::Float(_) | RlispNum::Ratio(..) => None,
         }
     }
+    // `self` as a numerator and denominator, or `None` if it is a
+    // `Float`
+    fn to_ratio(&self) -> Option<(BigInt, BigInt)> {
+        match *self {
+            RlispNum::Ratio(ref n, ref d) => Some((n.clone(), d.clone())),
+            _ => self.to_big().map(|b| (b, BigInt::from(1))),
+        }
+    }
+    // applies whichever of `int`, `big`, `ratio` or `float` suits
+    // `self` and `rhs`, following the usual contagion rules: an
+    // operation involving a float is done on floats, and otherwise
+    // one involving a ratio is done on ratios. `int` returning `None`
+    // means the result overflowed, so `big` is tried instead; `big`
+    // returning `None` means the result isn't an integer, so `ratio`
+    // is tried instead; and `ratio` returning `None` means there is
+    // no rational result, so `float` is tried instead.
+    fn arithmetic<I, B, R, F>(self, rhs: Self, int: I, big: B, ratio: R, float: F) -> Self
+    where
+        I: FnOnce(i32, i32) -> Option<i32>,
+        B: FnOnce(&BigInt, &BigInt) -> Option<BigInt>,
+        R: FnOnce((BigInt, BigInt), (BigInt, BigInt)) -> Option<RlispNum>,
+        F: FnOnce(f64, f64) -> f64,
+    {
+        if let (&RlispNum::Int(lhs), &RlispNum::Int(rhs)) = (&self, &rhs) {
+            if let Some(i) = int(lhs, rhs) {
+                return RlispNum::Int(i);
+            }
+        }
+        if let (Some(lhs), Some(rhs)) = (self.to_big(), rhs.to_big()) {
+            if let Some(b) = big(&lhs, &rhs) {
+                return demote(b);
+            }
+        }
+        if let (Some(lhs), Some(rhs)) = (self.to_ratio(), rhs.to_ratio()) {
+            if let Some(r) = ratio(lhs, rhs) {
+                return r;
+            }
+        }
+        RlispNum::Float(float(self.to_f64(), rhs.to_f64()))
+    }
 }
 
 impl convert::From<f64> for RlispNum {
@@ -79,12 +230,15 @@ impl convert::From<i32> for RlispNum {
     }
 }
 
+impl convert::From<BigInt> for RlispNum {
+    fn from(b: BigInt) -> Self {
+        demote(b)
+    }
+}
+
 impl convert::From<RlispNum> for f64 {
     fn from(n: RlispNum) -> f64 {
-        match n {
-            RlispNum::Int(i) => f64::from(i),
-            RlispNum::Float(f) => f,
-        }
+        n.to_f64()
     }
 }
 
@@ -101,64 +255,84 @@ impl MaybeFrom<RlispNum> for i32 {
 impl ops::Add for RlispNum {
     type Output = Self;
     fn add(self, rhs: Self) -> Self {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(self), i32::maybe_from(rhs)) {
-            RlispNum::Int(lhs + rhs)
-        } else {
-            RlispNum::Float(f64::from(self) + f64::from(rhs))
-        }
+        self.arithmetic(
+            rhs,
+            i32::checked_add,
+            |a, b| Some(a + b),
+            |(a, b), (c, d)| RlispNum::ratio(&(&(&a * &d) + &(&c * &b)), &(&b * &d)),
+            |a, b| a + b,
+        )
     }
 }
 
 impl ops::AddAssign for RlispNum {
     fn add_assign(&mut self, rhs: Self) {
-        *self = *self + rhs;
+        *self = mem::replace(self, RlispNum::Int(0)) + rhs;
     }
 }
 
 impl ops::Sub for RlispNum {
     type Output = Self;
     fn sub(self, rhs: Self) -> Self {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(self), i32::maybe_from(rhs)) {
-            RlispNum::Int(lhs - rhs)
-        } else {
-            RlispNum::Float(f64::from(self) - f64::from(rhs))
-        }
+        self.arithmetic(
+            rhs,
+            i32::checked_sub,
+            |a, b| Some(a - b),
+            |(a, b), (c, d)| RlispNum::ratio(&(&(&a * &d) - &(&c * &b)), &(&b * &d)),
+            |a, b| a - b,
+        )
     }
 }
 
 impl ops::SubAssign for RlispNum {
     fn sub_assign(&mut self, rhs: Self) {
-        *self = *self - rhs;
+        *self = mem::replace(self, RlispNum::Int(0)) - rhs;
     }
 }
 
 impl ops::Mul for RlispNum {
     type Output = Self;
     fn mul(self, rhs: Self) -> Self {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(self), i32::maybe_from(rhs)) {
-            RlispNum::Int(lhs * rhs)
-        } else {
-            RlispNum::Float(f64::from(self) * f64::from(rhs))
-        }
+        self.arithmetic(
+            rhs,
+            i32::checked_mul,
+            |a, b| Some(a * b),
+            |(a, b), (c, d)| RlispNum::ratio(&(&a * &c), &(&b * &d)),
+            |a, b| a * b,
+        )
     }
 }
 
 impl ops::MulAssign for RlispNum {
     fn mul_assign(&mut self, rhs: Self) {
-        *self = *self * rhs;
+        *self = mem::replace(self, RlispNum::Int(0)) * rhs;
     }
 }
 
 impl ops::Div for RlispNum {
     type Output = Self;
     fn div(self, rhs: Self) -> Self {
-        RlispNum::Float(f64::from(self) / f64::from(rhs))
+        // dividing rationals is exact, except by zero, which falls
+        // through to the float case
+        self.arithmetic(
+            rhs,
+            |a, b| match a.checked_rem(b) {
+                Some(0) => a.checked_div(b),
+                _ => None,
+            },
+            |a, b| match a.divrem(b) {
+                Some((quotient, ref remainder)) if remainder.is_zero() => Some(quotient),
+                _ => None,
+            },
+            |(a, b), (c, d)| RlispNum::ratio(&(&a * &d), &(&b * &c)),
+            |a, b| a / b,
+        )
     }
 }
 
 impl ops::DivAssign for RlispNum {
     fn div_assign(&mut self, rhs: Self) {
-        *self = *self / rhs;
+        *self = mem::replace(self, RlispNum::Int(0)) / rhs;
     }
 }
 
@@ -172,64 +346,47 @@ impl ops::Neg for RlispNum {
 impl ops::Rem for RlispNum {
     type Output = Self;
     fn rem(self, rhs: Self) -> Self {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(self), i32::maybe_from(rhs)) {
-            RlispNum::Int(lhs % rhs)
-        } else {
-            RlispNum::Float(f64::from(self) % f64::from(rhs))
-        }
+        // the remainder of `a/b` by `c/d` is `a/b - q*c/d`, where `q`
+        // is `(a*d) / (b*c)` rounded towards zero. A remainder by zero
+        // falls through to the float case, and is NaN rather than a
+        // panic.
+        self.arithmetic(
+            rhs,
+            i32::checked_rem,
+            |a, b| a.divrem(b).map(|(_, rem)| rem),
+            |(a, b), (c, d)| {
+                let (quotient, _) = (&a * &d).divrem(&(&b * &c))?;
+                let numerator = &(&a * &d) - &(&(&quotient * &c) * &b);
+                RlispNum::ratio(&numerator, &(&b * &d))
+            },
+            |a, b| a % b,
+        )
     }
 }
 
 impl ops::RemAssign for RlispNum {
     fn rem_assign(&mut self, rhs: Self) {
-        *self = *self % rhs;
+        *self = mem::replace(self, RlispNum::Int(0)) % rhs;
     }
 }
 
 impl cmp::PartialEq for RlispNum {
     fn eq(&self, rhs: &Self) -> bool {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(*self), i32::maybe_from(*rhs)) {
-            lhs == rhs
-        } else {
-            f64::from(*self) == f64::from(*rhs)
-        }
+        self.partial_cmp(rhs) == Some(cmp::Ordering::Equal)
     }
 }
 
 impl cmp::PartialOrd for RlispNum {
     fn partial_cmp(&self, rhs: &Self) -> Option<cmp::Ordering> {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(*self), i32::maybe_from(*rhs)) {
+        // rationals are compared exactly, and anything involving a
+        // float as floats
+        if let (&RlispNum::Int(lhs), &RlispNum::Int(rhs)) = (self, rhs) {
             lhs.partial_cmp(&rhs)
+        } else if let (Some((a, b)), Some((c, d))) = (self.to_ratio(), rhs.to_ratio()) {
+            // `b` and `d` are positive, so `a/b < c/d` iff `a*d < c*b`
+            (&a * &d).partial_cmp(&(&c * &b))
         } else {
-            f64::from(*self).partial_cmp(&f64::from(*rhs))
-        }
-    }
-    fn lt(&self, rhs: &Self) -> bool {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(*self), i32::maybe_from(*rhs)) {
-            lhs < rhs
-        } else {
-            f64::from(*self) < f64::from(*rhs)
-        }
-    }
-    fn le(&self, rhs: &Self) -> bool {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(*self), i32::maybe_from(*rhs)) {
-            lhs <= rhs
-        } else {
-            f64::from(*self) <= f64::from(*rhs)
-        }
-    }
-    fn gt(&self, rhs: &Self) -> bool {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(*self), i32::maybe_from(*rhs)) {
-            lhs > rhs
-        } else {
-            f64::from(*self) > f64::from(*rhs)
-        }
-    }
-    fn ge(&self, rhs: &Self) -> bool {
-        if let (Some(lhs), Some(rhs)) = (i32::maybe_from(*self), i32::maybe_from(*rhs)) {
-            lhs >= rhs
-        } else {
-            f64::from(*self) >= f64::from(*rhs)
+            self.to_f64().partial_cmp(&rhs.to_f64())
         }
     }
 }
@@ -240,6 +397,10 @@ impl MaybeFrom<Object> for RlispNum {
             Some(RlispNum::Float(f))
         } else if let Some(n) = i32::maybe_from(obj) {
             Some(RlispNum::Int(n))
+        } else if let Some(b) = <&RlispBignum>::maybe_from(obj) {
+            Some(RlispNum::Big(b.value.clone()))
+        } else if let Some(r) = <&RlispRatio>::maybe_from(obj) {
+            Some(RlispNum::Ratio(r.numerator.clone(), r.denominator.clone()))
         } else if let Some(place) = Place::maybe_from(obj) {
             RlispNum::maybe_from(*place)
         } else {
@@ -250,7 +411,8 @@ impl MaybeFrom<Object> for RlispNum {
 
 impl FromObject for RlispNum {
     fn is_type(obj: Object) -> bool {
-        i32::is_type(obj) || f64::is_type(obj)
+        i32::is_type(obj) || f64::is_type(obj) || <*const RlispBignum>::is_type(obj)
+            || <*const RlispRatio>::is_type(obj)
     }
     fn rlisp_type() -> RlispType {
         RlispType::Number
@@ -1,62 +1,153 @@
 use types::*;
+use gc::{GarbageCollected, GcMark};
 use std::{borrow, convert, fmt, ops};
 
+/// A reference to somewhere an Object is stored, which `setf` can
+/// store into. Either a pointer to a slot, like a variable's binding
+/// or the car of a cons, or an `ElementPlace`, which refers to an
+/// element of a vector or hash table by its index or key.
 #[derive(Copy, Clone, PartialEq, Eq)]
-pub struct Place(*mut Object);
+pub enum Place {
+    Slot(*mut Object),
+    Element(*mut ElementPlace),
+}
+
+/// An element of a vector or hash table, as returned by `vector-ref`
+/// and `gethash`. Reading it looks the element up in `container`
+/// each time, and writing it stores into `container`, so it stays
+/// valid when the container grows, and keeps the container from
+/// being gc'd. A key with no value in a hash table reads as
+/// `default`, and writing to it adds the key to the table.
+pub struct ElementPlace {
+    pub gc_marking: GcMark,
+    container: Object,
+    key: Object,
+    default: Object,
+}
+
+impl ElementPlace {
+    pub fn new(container: Object, key: Object, default: Object) -> Self {
+        debug_assert!(container.vectorp() || container.hash_tablep());
+        Self {
+            gc_marking: 0,
+            container,
+            key: key.deref_place(),
+            default: default.deref_place(),
+        }
+    }
+    fn get(&self) -> &Object {
+        unsafe {
+            if let Some(vector) = <&RlispVector>::maybe_from(self.container) {
+                let idx = i32::from_unchecked(self.key) as usize;
+                vector.get_ref(idx).unwrap_or(&self.default)
+            } else {
+                let table = <&RlispHashTable>::from_unchecked(self.container);
+                table.get_ref(self.key).unwrap_or(&self.default)
+            }
+        }
+    }
+    fn get_mut(&mut self) -> &mut Object {
+        unsafe {
+            if let Some(vector) = <&mut RlispVector>::maybe_from(self.container) {
+                let idx = i32::from_unchecked(self.key) as usize;
+                // vectors don't shrink, so the index checked by
+                // `vector-ref` is still in bounds
+                vector.get_mut(idx).unwrap()
+            } else {
+                let table = <&mut RlispHashTable>::from_unchecked(self.container);
+                table.entry(self.key, self.default)
+            }
+        }
+    }
+}
+
+impl GarbageCollected for ElementPlace {
+    fn my_marking(&self) -> &GcMark {
+        &self.gc_marking
+    }
+    fn my_marking_mut(&mut self) -> &mut GcMark {
+        &mut self.gc_marking
+    }
+    fn gc_mark_children(&mut self, mark: GcMark) {
+        self.container.gc_mark(mark);
+        self.key.gc_mark(mark);
+        self.default.gc_mark(mark);
+    }
+}
+
+impl convert::From<*mut ElementPlace> for Object {
+    fn from(place: *mut ElementPlace) -> Object {
+        Object(ObjectTag::ElementPlace.tag(place as u64))
+    }
+}
 
 impl borrow::Borrow<Object> for Place {
     fn borrow(&self) -> &Object {
-        unsafe { &*(self.0) }
+        self
     }
 }
 
 impl borrow::BorrowMut<Object> for Place {
     fn borrow_mut(&mut self) -> &mut Object {
-        unsafe { &mut *(self.0) }
+        self
     }
 }
 
 impl ops::Deref for Place {
     type Target = Object;
     fn deref(&self) -> &Object {
-        unsafe { &*(self.0) }
+        unsafe {
+            match *self {
+                Place::Slot(slot) => &*slot,
+                Place::Element(element) => (*element).get(),
+            }
+        }
     }
 }
 
 impl ops::DerefMut for Place {
     fn deref_mut(&mut self) -> &mut Object {
-        unsafe { &mut *(self.0) }
+        unsafe {
+            match *self {
+                Place::Slot(slot) => &mut *slot,
+                Place::Element(element) => (*element).get_mut(),
+            }
+        }
     }
 }
 
 impl fmt::Display for Place {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        unsafe { write!(f, "{}", *(self.0)) }
+        write!(f, "{}", **self)
     }
 }
 
 impl convert::From<*mut Object> for Place {
     fn from(obj: *mut Object) -> Self {
-        Place(obj)
+        Place::Slot(obj)
     }
 }
 
 impl<'any> convert::From<&'any mut Object> for Place {
     fn from(obj: &mut Object) -> Self {
-        Place(obj as *mut Object)
+        Place::Slot(obj as *mut Object)
     }
 }
 
 impl fmt::Debug for Place {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        unsafe { write!(f, "[place -> {:?}]", *(self.0)) }
+        write!(f, "[place -> {:?}]", **self)
     }
 }
 
 impl FromUnchecked<Object> for Place {
     unsafe fn from_unchecked(obj: Object) -> Place {
         debug_assert!(obj.placep());
-        Place(ObjectTag::Place.untag(obj.0) as _)
+        if ObjectTag::Place.is_of_type(obj.0) {
+            Place::Slot(ObjectTag::Place.untag(obj.0) as _)
+        } else {
+            Place::Element(ObjectTag::ElementPlace.untag(obj.0) as _)
+        }
     }
 }
 
@@ -81,6 +172,9 @@ impl MaybeFrom<Object> for Place {
 
 impl convert::From<Place> for Object {
     fn from(p: Place) -> Object {
-        Object(ObjectTag::Place.tag(p.0 as u64))
+        match p {
+            Place::Slot(slot) => Object(ObjectTag::Place.tag(slot as u64)),
+            Place::Element(element) => Object::from(element),
+        }
     }
 }
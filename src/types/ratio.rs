@@ -0,0 +1,65 @@
+/*
+An exact ratio of two integers, like `1/3`. As with `RlispBignum`,
+arithmetic is done on `RlispNum`s, which keep ratios in lowest terms
+with a positive denominator and turn any whose denominator is 1 back
+into integers, so an `RlispRatio` is never an integer in disguise.
+*/
+
+use std::fmt;
+use gc::{GarbageCollected, GcMark};
+use math::bigint::BigInt;
+use types::*;
+
+pub struct RlispRatio {
+    pub gc_marking: GcMark,
+    pub numerator: BigInt,
+    pub denominator: BigInt,
+}
+
+impl RlispRatio {
+    pub fn new(numerator: BigInt, denominator: BigInt) -> Self {
+        debug_assert!(denominator > BigInt::from(1));
+        Self {
+            gc_marking: 0,
+            numerator,
+            denominator,
+        }
+    }
+}
+
+impl GarbageCollected for RlispRatio {
+    fn my_marking(&self) -> &GcMark {
+        &self.gc_marking
+    }
+    fn my_marking_mut(&mut self) -> &mut GcMark {
+        &mut self.gc_marking
+    }
+    fn gc_mark_children(&mut self, _mark: GcMark) {
+        // `RlispRatio`s don't have any children, so this is a no-op
+    }
+}
+
+impl fmt::Display for RlispRatio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+impl fmt::Debug for RlispRatio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl FromUnchecked<Object> for *mut RlispRatio {
+    unsafe fn from_unchecked(obj: Object) -> *mut RlispRatio {
+        debug_assert!(obj.ratiop());
+        ObjectTag::Ratio.untag(obj.0) as *mut RlispRatio
+    }
+}
+
+impl FromObject for *mut RlispRatio {
+    fn rlisp_type() -> RlispType {
+        RlispType::Ratio
+    }
+}
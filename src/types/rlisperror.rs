@@ -1,10 +1,18 @@
 use std::{convert, fmt};
 use gc::{GarbageCollected, GcMark};
 use types::*;
+use lisp::SourceSpan;
 
 pub struct RlispError {
     pub gc_marking: GcMark,
     pub error: RlispErrorKind,
+    // the names of the functions which were being called when this
+    // error was signaled, innermost first. `None` until the
+    // evaluator records it.
+    pub backtrace: Option<Vec<Object>>,
+    // where in a source file the innermost form that signaled this
+    // error was read from, if it was read from one
+    pub location: Option<SourceSpan>,
 }
 
 impl RlispError {
@@ -29,9 +37,44 @@ impl RlispError {
     pub fn index_out_of_bounds(idx: Object, reciever: Object) -> Self {
         Self::from(RlispErrorKind::IndexOutOfBounds { idx, reciever })
     }
+    pub fn stack_depth_exceeded(max_depth: Object) -> Self {
+        Self::from(RlispErrorKind::StackDepthExceeded { max_depth })
+    }
+    pub fn unknown_keyword(key: Object, allowed: Object) -> Self {
+        Self::from(RlispErrorKind::UnknownKeyword { key, allowed })
+    }
+    pub fn missing_keyword_value(key: Object) -> Self {
+        Self::from(RlispErrorKind::MissingKeywordValue { key })
+    }
+    pub fn destructuring_mismatch(pattern: Object, value: Object) -> Self {
+        Self::from(RlispErrorKind::DestructuringMismatch { pattern, value })
+    }
     pub fn custom(kind: Object, info: Object) -> Self {
         Self::from(RlispErrorKind::Custom { kind, info })
     }
+    pub fn control_error(kind: Object, name: Object) -> Self {
+        Self::from(RlispErrorKind::ControlError { kind, name })
+    }
+    pub fn restart_invoked(name: Object, args: Object, id: usize) -> Self {
+        Self::from(RlispErrorKind::RestartInvoked { name, args, id })
+    }
+    pub fn return_from(name: Object, value: Object, id: usize) -> Self {
+        Self::from(RlispErrorKind::ReturnFrom { name, value, id })
+    }
+    pub fn throw(tag: Object, value: Object) -> Self {
+        Self::from(RlispErrorKind::Throw { tag, value })
+    }
+    /// Transfers of control, like invoking a restart, travel up the
+    /// stack as errors so that they unwind through `bubble!` like any
+    /// other, but they are not signaled and handlers don't catch them.
+    pub fn is_transfer(&self) -> bool {
+        match self.error {
+            RlispErrorKind::RestartInvoked { .. }
+            | RlispErrorKind::ReturnFrom { .. }
+            | RlispErrorKind::Throw { .. } => true,
+            _ => false,
+        }
+    }
 }
 
 impl convert::From<RlispErrorKind> for RlispError {
@@ -39,6 +82,8 @@ impl convert::From<RlispErrorKind> for RlispError {
         Self {
             gc_marking: 0,
             error,
+            backtrace: None,
+            location: None,
         }
     }
 }
@@ -48,6 +93,8 @@ impl convert::From<Error> for RlispError {
         Self {
             gc_marking: 0,
             error: RlispErrorKind::RustError(e),
+            backtrace: None,
+            location: None,
         }
     }
 }
@@ -59,12 +106,50 @@ impl GarbageCollected for RlispError {
     fn my_marking_mut(&mut self) -> &mut GcMark {
         &mut self.gc_marking
     }
-    fn gc_mark_children(&mut self, _: GcMark) {}
+    fn gc_mark_children(&mut self, mark: GcMark) {
+        // every `Object` an error holds is marked, even those which
+        // are usually symbols or immediates, so that an error never
+        // outlives the objects it refers to
+        match self.error {
+            RlispErrorKind::ImproperList | RlispErrorKind::RustError(_) => (),
+            RlispErrorKind::UnboundSymbol { sym: obj }
+            | RlispErrorKind::NotAType { found: obj }
+            | RlispErrorKind::UndefinedSymbol { sym: obj }
+            | RlispErrorKind::StackDepthExceeded { max_depth: obj }
+            | RlispErrorKind::MissingKeywordValue { key: obj } => obj.gc_mark(mark),
+            RlispErrorKind::WrongType { wanted: a, found: b }
+            | RlispErrorKind::Custom { kind: a, info: b }
+            | RlispErrorKind::IndexOutOfBounds { idx: a, reciever: b }
+            | RlispErrorKind::ControlError { kind: a, name: b }
+            | RlispErrorKind::UnknownKeyword { key: a, allowed: b }
+            | RlispErrorKind::DestructuringMismatch { pattern: a, value: b }
+            | RlispErrorKind::RestartInvoked { name: a, args: b, .. }
+            | RlispErrorKind::ReturnFrom { name: a, value: b, .. }
+            | RlispErrorKind::Throw { tag: a, value: b } => {
+                a.gc_mark(mark);
+                b.gc_mark(mark);
+            }
+            RlispErrorKind::BadArgsCount { min, max, found } => {
+                min.gc_mark(mark);
+                max.gc_mark(mark);
+                found.gc_mark(mark);
+            }
+        }
+        if let Some(ref backtrace) = self.backtrace {
+            for name in backtrace {
+                name.gc_mark(mark);
+            }
+        }
+    }
 }
 
 impl fmt::Display for RlispError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "ERROR: {}", self.error)
+        if let Some(ref location) = self.location {
+            write!(f, "ERROR: {}: {}", location, self.error)
+        } else {
+            write!(f, "ERROR: {}", self.error)
+        }
     }
 }
 
@@ -97,6 +182,46 @@ pub enum RlispErrorKind {
         idx: Object,
         reciever: Object,
     },
+    StackDepthExceeded {
+        max_depth: Object,
+    },
+    // an attempt to exit to a restart, block or catch tag (`kind`)
+    // which isn't active
+    ControlError {
+        kind: Object,
+        name: Object,
+    },
+    // a keyword arg which doesn't name one of the `&key` parameters
+    // (`allowed`) of the function it was passed to
+    UnknownKeyword {
+        key: Object,
+        allowed: Object,
+    },
+    MissingKeywordValue {
+        key: Object,
+    },
+    // a value which doesn't have the shape of the nested arglist
+    // `pattern` it was to be bound to
+    DestructuringMismatch {
+        pattern: Object,
+        value: Object,
+    },
+    RestartInvoked {
+        name: Object,
+        args: Object,
+        // identifies the `restart-case` which established the restart
+        id: usize,
+    },
+    ReturnFrom {
+        name: Object,
+        value: Object,
+        // identifies the `block` being returned from
+        id: usize,
+    },
+    Throw {
+        tag: Object,
+        value: Object,
+    },
 }
 
 impl RlispErrorKind {
@@ -133,6 +258,34 @@ impl fmt::Display for RlispErrorKind {
             RlispErrorKind::IndexOutOfBounds { idx, reciever } => {
                 write!(f, "{} is not a valid index into {}", idx, reciever)
             }
+            RlispErrorKind::StackDepthExceeded { max_depth } => write!(
+                f,
+                "exceeded the maximum evaluation depth of {}",
+                max_depth
+            ),
+            RlispErrorKind::ControlError { kind, name } => {
+                write!(f, "no {} named {} is active", kind, name)
+            }
+            RlispErrorKind::UnknownKeyword { key, allowed } => {
+                if allowed.nilp() {
+                    write!(f, "found the keyword arg {} but wanted no keyword args", key)
+                } else {
+                    write!(f, "found the keyword arg {} but wanted one of {}", key, allowed)
+                }
+            }
+            RlispErrorKind::MissingKeywordValue { key } => {
+                write!(f, "found no value for the keyword arg {}", key)
+            }
+            RlispErrorKind::DestructuringMismatch { pattern, value } => {
+                write!(f, "expected a list matching {} but found {}", pattern, value)
+            }
+            RlispErrorKind::RestartInvoked { name, .. } => {
+                write!(f, "restart {} was invoked outside of its restart-case", name)
+            }
+            RlispErrorKind::ReturnFrom { name, .. } => {
+                write!(f, "returned from block {} outside of it", name)
+            }
+            RlispErrorKind::Throw { tag, .. } => write!(f, "threw to {} outside of a catch", tag),
         }
     }
 }
@@ -0,0 +1,62 @@
+/*
+A growable string, for building up a string piece by piece. Unlike
+`RlispString`, whose contents are allocated along with it and can't
+change, a `RlispStringBuilder` owns a `String`, which is freed when it
+is gc'd.
+*/
+
+use std::fmt;
+use gc::{GarbageCollected, GcMark};
+use types::*;
+
+#[derive(Default)]
+pub struct RlispStringBuilder {
+    pub gc_marking: GcMark,
+    pub contents: String,
+}
+
+impl<'a> convert::From<&'a str> for RlispStringBuilder {
+    fn from(contents: &str) -> Self {
+        Self {
+            gc_marking: 0,
+            contents: String::from(contents),
+        }
+    }
+}
+
+impl GarbageCollected for RlispStringBuilder {
+    fn my_marking(&self) -> &GcMark {
+        &self.gc_marking
+    }
+    fn my_marking_mut(&mut self) -> &mut GcMark {
+        &mut self.gc_marking
+    }
+    fn gc_mark_children(&mut self, _mark: GcMark) {
+        // the contents aren't Objects, so this is a no-op
+    }
+}
+
+impl fmt::Display for RlispStringBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<string-builder {:?}>", self.contents)
+    }
+}
+
+impl fmt::Debug for RlispStringBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl FromUnchecked<Object> for *mut RlispStringBuilder {
+    unsafe fn from_unchecked(obj: Object) -> *mut RlispStringBuilder {
+        debug_assert!(obj.string_builderp());
+        ObjectTag::StringBuilder.untag(obj.0) as *mut RlispStringBuilder
+    }
+}
+
+impl FromObject for *mut RlispStringBuilder {
+    fn rlisp_type() -> RlispType {
+        RlispType::StringBuilder
+    }
+}
@@ -0,0 +1,94 @@
+/*
+A growable, heap-allocated vector of Objects, read as `#(A B C)`.
+*/
+
+use std::{convert, fmt};
+use gc::{GarbageCollected, GcMark};
+use types::*;
+
+#[derive(Default)]
+pub struct RlispVector {
+    pub gc_marking: GcMark,
+    pub elements: Vec<Object>,
+}
+
+impl convert::From<Vec<Object>> for RlispVector {
+    fn from(elements: Vec<Object>) -> Self {
+        Self {
+            gc_marking: 0,
+            elements,
+        }
+    }
+}
+
+impl RlispVector {
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+    pub fn push(&mut self, obj: Object) {
+        self.elements.push(obj);
+    }
+    /// the element at `idx`, if there is one
+    pub fn get_ref(&self, idx: usize) -> Option<&Object> {
+        self.elements.get(idx)
+    }
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut Object> {
+        self.elements.get_mut(idx)
+    }
+}
+
+impl GarbageCollected for RlispVector {
+    fn my_marking(&self) -> &GcMark {
+        &self.gc_marking
+    }
+    fn my_marking_mut(&mut self) -> &mut GcMark {
+        &mut self.gc_marking
+    }
+    fn gc_mark_children(&mut self, mark: GcMark) {
+        for obj in &self.elements {
+            obj.gc_mark(mark);
+        }
+    }
+}
+
+impl fmt::Display for RlispVector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#(")?;
+        for (i, obj) in self.elements.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", obj)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Debug for RlispVector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#(")?;
+        for (i, obj) in self.elements.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:?}", obj)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl FromUnchecked<Object> for *mut RlispVector {
+    unsafe fn from_unchecked(obj: Object) -> *mut RlispVector {
+        debug_assert!(obj.vectorp());
+        ObjectTag::Vector.untag(obj.0) as *mut RlispVector
+    }
+}
+
+impl FromObject for *mut RlispVector {
+    fn rlisp_type() -> RlispType {
+        RlispType::Vector
+    }
+}